@@ -2,6 +2,7 @@ use nix::sys::termios::{FlushArg, Termios, cfmakeraw, tcflush, tcgetattr, tcseta
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::stdin;
+use std::os::fd::{AsRawFd, RawFd};
 use std::os::raw::c_void;
 use std::os::unix::fs::OpenOptionsExt;
 use std::ptr::{null, null_mut};
@@ -15,19 +16,29 @@ use gbm::{
     Surface,
 };
 use input::event::KeyboardEvent;
+use input::event::pointer::{Axis, ButtonState, PointerEvent, PointerScrollEvent};
+use input::event::touch::{TouchEvent, TouchEventPosition, TouchEventSlot};
 use input::ffi::{libinput_event_keyboard_get_key, libinput_event_keyboard_get_key_state};
 use input::{AsRaw, Libinput, LibinputInterface};
 use libc::{O_RDONLY, O_RDWR, O_WRONLY};
 use xkbcommon_rs::xkb_context::ContextFlags;
 use xkbcommon_rs::xkb_keymap::CompileFlags;
+use xkbcommon_rs::xkb_state::KeyDirection;
 use xkbcommon_rs::{Context, Keymap};
 
 use crate::backend::Window;
+use crate::backend::events;
+use crate::backend::input::{InputState, Wheel, sdl_button};
+use crate::backend::keys::PressedKeys;
+use crate::backend::queue::Queue;
+use crate::backend::remap::{Input, Remap};
+use crate::backend::repeat::KeyRepeat;
+use crate::backend::touch::{TouchAction, TouchMouse};
+use crate::backend::unicode::Unicode;
+use crate::backend::video::VideoModes;
 use crate::egl::{EGL, EGLDisplay, EGLSurface, EGLWindowType};
-use crate::type_defs::{
-    SDL_EventType, SDL_Rect, SDL_Surface, SDL_keysym, SDLKey, SDLKey_SDLK_LAST,
-};
-use crate::xcb::sdl_key_from_keysym;
+use crate::joystick::Joysticks;
+use crate::type_defs::{SDL_BUTTON_WHEELDOWN, SDL_BUTTON_WHEELUP, SDL_Rect, SDL_Surface};
 
 #[derive(Debug)]
 struct Card(std::fs::File);
@@ -89,7 +100,6 @@ impl LibinputInterface for InputInterface {
 pub struct DRMWindow {
     card: Arc<Card>,
     fake_surface: SDL_Surface,
-    keys: [SDLKey; SDLKey_SDLK_LAST as usize],
     egl: EGL,
     surface: EGLSurface,
     gbm_device: GBMDevice<Arc<Card>>,
@@ -104,13 +114,21 @@ pub struct DRMWindow {
     // plane: drm::control::plane::Handle,
     // plane_properties: HashMap<String, property::Info>,
     input: Libinput,
-    xkb_keymap: Keymap,
     xkb_state: xkbcommon_rs::State,
-    pending_keys: Vec<(u32, SDL_keysym)>,
+    events: Queue,
+    input_state: InputState,
+    wheel: Wheel,
+    touch: TouchMouse,
+    joysticks: Joysticks,
+    key_repeat: KeyRepeat,
+    pressed_keys: PressedKeys,
+    unicode: Unicode,
+    remap: Remap,
 
     framebuffers: HashMap<u64, drm::control::framebuffer::Handle>,
 
     termios: Termios,
+    video_modes: VideoModes,
 }
 
 impl DRMWindow {
@@ -215,7 +233,7 @@ impl DRMWindow {
             CompileFlags::NO_FLAGS,
         )
         .unwrap();
-        let xkb_state = xkbcommon_rs::State::new(xkb_keymap.clone());
+        let xkb_state = xkbcommon_rs::State::new(xkb_keymap);
 
         let crtc_properties = card
             .get_properties(crtc)?
@@ -230,7 +248,6 @@ impl DRMWindow {
         Ok(Self {
             card: card.clone(),
             fake_surface,
-            keys: [0; SDLKey_SDLK_LAST as usize],
             egl,
             surface,
             display,
@@ -240,10 +257,18 @@ impl DRMWindow {
             crtc,
             framebuffers: HashMap::new(),
             input,
-            xkb_keymap,
             xkb_state,
-            pending_keys: vec![],
+            events: Queue::default(),
+            input_state: InputState::default(),
+            wheel: Wheel::default(),
+            touch: TouchMouse::default(),
+            joysticks: Joysticks::default(),
+            key_repeat: KeyRepeat::default(),
+            pressed_keys: PressedKeys::default(),
+            unicode: Unicode::default(),
+            remap: Remap::default(),
             termios: tcgetattr(stdin()).unwrap(),
+            video_modes: VideoModes::new(),
             // crtc_properties,
             // plane,
             // plane_properties,
@@ -252,7 +277,8 @@ impl DRMWindow {
 
     fn handle_libinput(&mut self) {
         self.input.dispatch().unwrap();
-        for ev in &mut self.input {
+        let input_events: Vec<_> = self.input.by_ref().collect();
+        for ev in input_events {
             match ev {
                 input::Event::Keyboard(keyboard_event) => match keyboard_event {
                     KeyboardEvent::Key(keyboard_key_event) => {
@@ -266,34 +292,161 @@ impl DRMWindow {
                             )
                         };
 
-                        let layout = self.xkb_state.key_get_layout(key).unwrap();
-                        let level = self.xkb_keymap.num_levels_for_key(key, layout) - 1;
-                        let syms_out = self
-                            .xkb_keymap
-                            .key_get_syms_by_level(key, layout, level)
-                            .unwrap();
-
-                        for sym in syms_out {
-                            let key = sdl_key_from_keysym(sym);
-                            // println!("{}", key);
-                            self.pending_keys.push((
-                                state,
-                                SDL_keysym {
-                                    scancode: 25,
-                                    sym: key,
-                                    mod_: 0,
-                                    unicode: 0,
-                                },
-                            ));
-                            self.keys[key as usize] = state;
-                        }
+                        let pressed = state != 0;
+                        let (input, unicode) = if pressed {
+                            let sym = self.pressed_keys.press(&self.xkb_state, key);
+                            let unicode = self.unicode.translate(&self.xkb_state, key);
+                            let input = self
+                                .remap
+                                .press(Input::Key(sym), self.input_state.modifiers().get());
+                            (input, if input == Input::Key(sym) { unicode } else { 0 })
+                        } else {
+                            let sym = self.pressed_keys.release(&self.xkb_state, key);
+                            (self.remap.release(Input::Key(sym)), 0)
+                        };
+                        self.send_input(input, pressed, key, unicode);
+
+                        // We're the only ones keeping xkb's state here, unlike on Wayland.
+                        let direction = if pressed {
+                            KeyDirection::Down
+                        } else {
+                            KeyDirection::Up
+                        };
+                        self.xkb_state.update_key(key, direction);
+                        self.input_state.modifiers().sync(&self.xkb_state);
                     }
                     _ => {}
                 },
+                // There's no compositor moving a pointer for us, so it's ours to keep on the screen.
+                input::Event::Pointer(PointerEvent::Motion(motion)) => {
+                    self.input_state
+                        .raw_motion(motion.dx_unaccelerated(), motion.dy_unaccelerated());
+                    let (x, y) = self.input_state.pointer_position();
+                    let (w, h) = (self.fake_surface.w as f64, self.fake_surface.h as f64);
+                    // Not clamp(), that panics if the game set a 0x0 mode.
+                    let event = self.input_state.pointer_motion(
+                        (x + motion.dx()).min(w - 1.0).max(0.0),
+                        (y + motion.dy()).min(h - 1.0).max(0.0),
+                    );
+                    self.events.push(event);
+                }
+                input::Event::Pointer(PointerEvent::MotionAbsolute(motion)) => {
+                    let event = self.input_state.pointer_motion(
+                        motion.absolute_x_transformed(self.fake_surface.w as u32),
+                        motion.absolute_y_transformed(self.fake_surface.h as u32),
+                    );
+                    self.events.push(event);
+                }
+                input::Event::Pointer(PointerEvent::Button(button_event)) => {
+                    if let Some(button) = sdl_button(button_event.button()) {
+                        let pressed = button_event.button_state() == ButtonState::Pressed;
+                        self.send_button(button, pressed);
+                    }
+                }
+                // libinput also sends the older axis events, but these say more.
+                input::Event::Pointer(PointerEvent::ScrollWheel(scroll))
+                    if scroll.has_axis(Axis::Vertical) =>
+                {
+                    self.wheel.v120(scroll.scroll_value_v120(Axis::Vertical));
+                    self.send_wheel();
+                }
+                input::Event::Pointer(PointerEvent::ScrollFinger(scroll))
+                    if scroll.has_axis(Axis::Vertical) =>
+                {
+                    self.wheel.smooth(scroll.scroll_value(Axis::Vertical));
+                    self.send_wheel();
+                }
+                input::Event::Pointer(PointerEvent::ScrollContinuous(scroll))
+                    if scroll.has_axis(Axis::Vertical) =>
+                {
+                    self.wheel.smooth(scroll.scroll_value(Axis::Vertical));
+                    self.send_wheel();
+                }
+                input::Event::Touch(TouchEvent::Down(down)) => {
+                    let (x, y) = self.touch_position(&down);
+                    let actions = self.touch.down(down.seat_slot() as i32, x, y);
+                    self.send_touch(actions);
+                }
+                input::Event::Touch(TouchEvent::Motion(motion)) => {
+                    let (x, y) = self.touch_position(&motion);
+                    if let Some(action) = self.touch.motion(motion.seat_slot() as i32, x, y) {
+                        self.send_touch(vec![action]);
+                    }
+                }
+                input::Event::Touch(TouchEvent::Up(up)) => {
+                    let actions = self.touch.up(up.seat_slot() as i32);
+                    self.send_touch(actions);
+                }
+                input::Event::Touch(TouchEvent::Cancel(_)) => {
+                    let actions = self.touch.cancel();
+                    self.send_touch(actions);
+                }
                 _ => {}
             }
         }
     }
+
+    // libinput gives us where on the whole screen it was, and the game covers all of it.
+    fn touch_position(&self, event: &impl TouchEventPosition) -> (i32, i32) {
+        (
+            event.x_transformed(self.fake_surface.w as u32) as i32,
+            event.y_transformed(self.fake_surface.h as u32) as i32,
+        )
+    }
+
+    fn send_touch(&mut self, actions: Vec<TouchAction>) {
+        for action in actions {
+            match action {
+                TouchAction::Motion(x, y) => {
+                    let event = self.input_state.motion(x, y);
+                    self.events.push(event);
+                }
+                TouchAction::Button(button, pressed) => self.send_button(button, pressed),
+            }
+        }
+    }
+
+    fn send_button(&mut self, button: u8, pressed: bool) {
+        let input = if pressed {
+            self.remap
+                .press(Input::Button(button), self.input_state.modifiers().get())
+        } else {
+            self.remap.release(Input::Button(button))
+        };
+        self.send_input(input, pressed, 0, 0);
+    }
+
+    fn send_wheel(&mut self) {
+        let clicks = self.wheel.frame();
+        let button = if clicks < 0 {
+            SDL_BUTTON_WHEELUP
+        } else {
+            SDL_BUTTON_WHEELDOWN
+        };
+        for _ in 0..clicks.abs() {
+            self.send_button(button, true);
+            self.send_button(button, false);
+        }
+    }
+
+    // Same as on Wayland.
+    fn send_input(&mut self, input: Input, pressed: bool, keycode: u32, unicode: u16) {
+        match input {
+            Input::Key(sym) => {
+                let keysym = self.input_state.key(sym, pressed, keycode, unicode);
+                self.events.push(events::key_event(pressed, keysym));
+                if pressed {
+                    self.key_repeat.press(keycode, keysym);
+                } else {
+                    self.key_repeat.release(keycode);
+                }
+            }
+            Input::Button(button) => {
+                let event = self.input_state.button(button, pressed);
+                self.events.push(event);
+            }
+        }
+    }
 }
 
 impl Window for DRMWindow {
@@ -314,24 +467,6 @@ impl Window for DRMWindow {
         null()
     }
 
-    fn get_key_state(&mut self, numkeys: *mut i32) -> *mut u8 {
-        if numkeys != null_mut() {
-            unsafe {
-                *numkeys = SDLKey_SDLK_LAST as i32;
-            }
-        }
-        return self.keys.as_mut_ptr() as *mut u8;
-    }
-
-    fn get_mouse_state(&mut self, x: *mut i32, y: *mut i32) -> u8 {
-        // todo!()
-        unsafe {
-            *x = 0;
-            *y = 0;
-        };
-        return 0;
-    }
-
     fn egl(&self) -> &EGL {
         &self.egl
     }
@@ -348,24 +483,48 @@ impl Window for DRMWindow {
         self.surface
     }
 
-    fn poll_event(&mut self, event: *mut crate::type_defs::SDL_Event) -> i32 {
+    fn video_modes(&mut self) -> &mut VideoModes {
+        &mut self.video_modes
+    }
+    fn key_repeat(&mut self) -> &mut KeyRepeat {
+        &mut self.key_repeat
+    }
+    fn input(&mut self) -> &mut InputState {
+        &mut self.input_state
+    }
+    fn unicode(&mut self) -> &mut Unicode {
+        &mut self.unicode
+    }
+    fn joysticks(&mut self) -> &mut Joysticks {
+        &mut self.joysticks
+    }
+    fn events(&mut self) -> &mut Queue {
+        &mut self.events
+    }
+    fn display_size(&mut self) -> Option<(i32, i32)> {
+        // We always scan out in the connector's first (preferred) mode.
+        let (w, h) = self.connector.modes().first()?.size();
+        Some((w as i32, h as i32))
+    }
+    fn display_modes(&mut self) -> Vec<(i32, i32)> {
+        // The connector lists more, but that's the only one we ever scan out in.
+        self.display_size().into_iter().collect()
+    }
+    fn surface_size(&mut self) -> (i32, i32) {
+        (self.fake_surface.w, self.fake_surface.h)
+    }
+
+    fn pump_events(&mut self) {
         self.handle_libinput();
-        unsafe {
-            if self.pending_keys.len() >= 1 {
-                if let Some(ev) = self.pending_keys.pop() {
-                    if ev.0 == 0 {
-                        (*event).key.type_ = SDL_EventType::SDL_KEYUP as u8;
-                    } else {
-                        (*event).key.type_ = SDL_EventType::SDL_KEYDOWN as u8;
-                    }
-                    (*event).key.which = 0;
-                    (*event).key.state = ev.0 as u8;
-                    (*event).key.keysym = ev.1;
-                }
-                return 1;
-            }
+        self.joystick_update();
+        // libinput doesn't repeat keys, so this is the only repeat we get.
+        if let Some(keysym) = self.key_repeat.check() {
+            self.events.push(events::key_event(true, keysym));
         }
-        return 0;
+    }
+
+    fn input_fds(&mut self) -> Vec<RawFd> {
+        vec![self.input.as_raw_fd()]
     }
 
     fn set_video_mode(
@@ -375,6 +534,11 @@ impl Window for DRMWindow {
         bpp: i32,
         flags: u32,
     ) -> *mut crate::type_defs::SDL_Surface {
+        // Touches are scaled to this, so it has to be the size the game thinks it has.
+        self.fake_surface.w = width;
+        self.fake_surface.h = height;
+        self.fake_surface.clip_rect.w = width as u16;
+        self.fake_surface.clip_rect.h = height as u16;
         return &mut self.fake_surface;
     }
 
@@ -0,0 +1,120 @@
+use crate::type_defs::{
+    SDL_Event, SDL_Event__bindgen_ty_2, SDL_Event__bindgen_ty_3, SDL_Event__bindgen_ty_4,
+    SDL_Event__bindgen_ty_5, SDL_Event__bindgen_ty_6, SDL_Event__bindgen_ty_7,
+    SDL_Event__bindgen_ty_8, SDL_Event__bindgen_ty_11, SDL_EventType, SDL_keysym,
+};
+
+pub fn key_event(pressed: bool, keysym: SDL_keysym) -> SDL_Event {
+    let type_ = if pressed {
+        SDL_EventType::SDL_KEYDOWN
+    } else {
+        SDL_EventType::SDL_KEYUP
+    };
+    SDL_Event {
+        key: SDL_Event__bindgen_ty_2 {
+            type_: type_ as u8,
+            which: 0,
+            state: pressed as u8,
+            keysym,
+        },
+    }
+}
+
+pub fn button_event(pressed: bool, button: u8, x: u16, y: u16) -> SDL_Event {
+    let type_ = if pressed {
+        SDL_EventType::SDL_MOUSEBUTTONDOWN
+    } else {
+        SDL_EventType::SDL_MOUSEBUTTONUP
+    };
+    SDL_Event {
+        button: SDL_Event__bindgen_ty_4 {
+            type_: type_ as u8,
+            which: 0,
+            button,
+            state: pressed as u8,
+            x,
+            y,
+        },
+    }
+}
+
+/// `buttons` is the button mask, like SDL_GetMouseState returns it.
+pub fn motion_event(buttons: u8, x: u16, y: u16, xrel: i16, yrel: i16) -> SDL_Event {
+    SDL_Event {
+        motion: SDL_Event__bindgen_ty_3 {
+            type_: SDL_EventType::SDL_MOUSEMOTION as u8,
+            which: 0,
+            state: buttons,
+            x,
+            y,
+            xrel,
+            yrel,
+        },
+    }
+}
+
+pub fn joy_axis_event(which: u8, axis: u8, value: i16) -> SDL_Event {
+    SDL_Event {
+        jaxis: SDL_Event__bindgen_ty_5 {
+            type_: SDL_EventType::SDL_JOYAXISMOTION as u8,
+            which,
+            axis,
+            value,
+        },
+    }
+}
+
+pub fn joy_ball_event(which: u8, ball: u8, xrel: i16, yrel: i16) -> SDL_Event {
+    SDL_Event {
+        jball: SDL_Event__bindgen_ty_6 {
+            type_: SDL_EventType::SDL_JOYBALLMOTION as u8,
+            which,
+            ball,
+            xrel,
+            yrel,
+        },
+    }
+}
+
+pub fn joy_hat_event(which: u8, hat: u8, value: u8) -> SDL_Event {
+    SDL_Event {
+        jhat: SDL_Event__bindgen_ty_7 {
+            type_: SDL_EventType::SDL_JOYHATMOTION as u8,
+            which,
+            hat,
+            value,
+        },
+    }
+}
+
+pub fn joy_button_event(which: u8, button: u8, pressed: bool) -> SDL_Event {
+    let type_ = if pressed {
+        SDL_EventType::SDL_JOYBUTTONDOWN
+    } else {
+        SDL_EventType::SDL_JOYBUTTONUP
+    };
+    SDL_Event {
+        jbutton: SDL_Event__bindgen_ty_8 {
+            type_: type_ as u8,
+            which,
+            button,
+            state: pressed as u8,
+        },
+    }
+}
+
+pub fn quit_event() -> SDL_Event {
+    SDL_Event {
+        quit: SDL_Event__bindgen_ty_11 {
+            type_: SDL_EventType::SDL_QUIT as u8,
+        },
+    }
+}
+
+/// SDL_BUTTON(): the bit a button has in the mask.
+pub fn button_mask(button: u8) -> u8 {
+    match button {
+        1..=8 => 1 << (button - 1),
+        _ => 0,
+    }
+}
@@ -0,0 +1,175 @@
+use image::{RgbaImage, imageops::FilterType};
+
+use crate::type_defs::{SDL_PixelFormat, SDL_SRCCOLORKEY, SDL_Surface};
+
+/// Reads the game's icon surface into plain RGBA, whatever format it's in. `mask` is SDL's 1-bit
+/// transparency mask (MSB first, rows padded to a byte), and may be null.
+pub unsafe fn surface_to_rgba(surface: *const SDL_Surface, mask: *const u8) -> Option<RgbaImage> {
+    let surface = surface.as_ref()?;
+    let format = surface.format.as_ref()?;
+    if surface.pixels.is_null() || surface.w <= 0 || surface.h <= 0 {
+        return None;
+    }
+    let (w, h) = (surface.w as u32, surface.h as u32);
+    let pixels = surface.pixels as *const u8;
+    let colorkey = (surface.flags & SDL_SRCCOLORKEY != 0).then_some(format.colorkey);
+
+    let mut img = RgbaImage::new(w, h);
+    for y in 0..h {
+        let row = pixels.add(y as usize * surface.pitch as usize);
+        for x in 0..w {
+            let p = row.add(x as usize * format.BytesPerPixel as usize);
+            let raw = match format.BytesPerPixel {
+                1 => *p as u32,
+                2 => u16::from_ne_bytes([*p, *p.add(1)]) as u32,
+                3 => u32::from_le_bytes([*p, *p.add(1), *p.add(2), 0]),
+                4 => u32::from_ne_bytes([*p, *p.add(1), *p.add(2), *p.add(3)]),
+                _ => return None,
+            };
+
+            let [r, g, b, mut a] = decode_pixel(format, raw);
+            if colorkey == Some(raw) {
+                a = 0;
+            }
+            if !mask.is_null() {
+                let byte = *mask.add(y as usize * w.div_ceil(8) as usize + x as usize / 8);
+                if byte & (0x80 >> (x % 8)) == 0 {
+                    a = 0;
+                }
+            }
+            img.put_pixel(x, y, image::Rgba([r, g, b, a]));
+        }
+    }
+    Some(img)
+}
+
+unsafe fn decode_pixel(format: &SDL_PixelFormat, raw: u32) -> [u8; 4] {
+    if format.BytesPerPixel == 1 {
+        if let Some(palette) = format.palette.as_ref()
+            && (raw as i32) < palette.ncolors
+            && !palette.colors.is_null()
+        {
+            let color = *palette.colors.add(raw as usize);
+            return [color.r, color.g, color.b, 255];
+        }
+        // No palette means greyscale as far as SDL is concerned.
+        return [raw as u8, raw as u8, raw as u8, 255];
+    }
+    [
+        channel(raw, format.Rmask, format.Rshift, format.Rloss, 0),
+        channel(raw, format.Gmask, format.Gshift, format.Gloss, 0),
+        channel(raw, format.Bmask, format.Bshift, format.Bloss, 0),
+        channel(raw, format.Amask, format.Ashift, format.Aloss, 255),
+    ]
+}
+
+/// Pulls a channel out and stretches it to 8 bits, so 5-bit white is 255 and not 248.
+fn channel(raw: u32, mask: u32, shift: u8, loss: u8, missing: u8) -> u8 {
+    if mask == 0 {
+        return missing;
+    }
+    let bits = 8 - loss.min(7) as u32;
+    let value = (raw & mask) >> shift;
+    (value * 255 / ((1 << bits) - 1)) as u8
+}
+
+pub fn scaled(img: &RgbaImage, size: u32) -> RgbaImage {
+    if img.width() == size && img.height() == size {
+        return img.clone();
+    }
+    image::imageops::resize(img, size, size, FilterType::Triangle)
+}
+
+/// Premultiplied, little-endian ARGB8888, which is what wl_shm's Argb8888 means. Writing straight
+/// RGBA into it swaps red and blue (which used to be papered over with a hue rotation).
+pub fn to_argb8888(img: &RgbaImage) -> Vec<u8> {
+    img.pixels()
+        .flat_map(|p| {
+            let [r, g, b, a] = p.0;
+            let premultiply = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+            [premultiply(b), premultiply(g), premultiply(r), a]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::null_mut;
+
+    use super::*;
+    use crate::type_defs::SDL_Rect;
+
+    fn argb_format() -> SDL_PixelFormat {
+        SDL_PixelFormat {
+            palette: null_mut(),
+            BitsPerPixel: 32,
+            BytesPerPixel: 4,
+            Rloss: 0,
+            Gloss: 0,
+            Bloss: 0,
+            Aloss: 0,
+            Rshift: 16,
+            Gshift: 8,
+            Bshift: 0,
+            Ashift: 24,
+            Rmask: 0x00FF0000,
+            Gmask: 0x0000FF00,
+            Bmask: 0x000000FF,
+            Amask: 0xFF000000,
+            colorkey: 0,
+            alpha: 255,
+        }
+    }
+
+    #[test]
+    fn channels() {
+        // RGB565 white and a mid green.
+        assert_eq!(channel(0xFFFF, 0xF800, 11, 3, 0), 255);
+        assert_eq!(channel(0xFFFF, 0x07E0, 5, 2, 0), 255);
+        assert_eq!(channel(0x0400, 0x07E0, 5, 2, 0), 129);
+        assert_eq!(channel(0, 0xF800, 11, 3, 0), 0);
+        // No alpha mask means opaque.
+        assert_eq!(channel(0x1234, 0, 0, 8, 255), 255);
+    }
+
+    #[test]
+    fn premultiplied() {
+        let mut img = RgbaImage::new(2, 1);
+        img.put_pixel(0, 0, image::Rgba([255, 128, 0, 255]));
+        img.put_pixel(1, 0, image::Rgba([255, 128, 0, 128]));
+        assert_eq!(to_argb8888(&img), [0, 128, 255, 255, 0, 64, 128, 128]);
+    }
+
+    #[test]
+    fn mask() {
+        let mut format = argb_format();
+        let mut pixels: [u32; 4] = [0xFFFF0000, 0xFF00FF00, 0x800000FF, 0xFFFFFFFF];
+        let surface = SDL_Surface {
+            flags: 0,
+            format: &mut format,
+            w: 2,
+            h: 2,
+            pitch: 8,
+            pixels: pixels.as_mut_ptr().cast(),
+            clip_rect: SDL_Rect {
+                x: 0,
+                y: 0,
+                w: 2,
+                h: 2,
+            },
+            refcount: 1,
+        };
+        // One byte per row, only the first pixel of the first row and the second of the second.
+        let mask = [0b1000_0000, 0b0100_0000];
+
+        let img = unsafe { surface_to_rgba(&surface, mask.as_ptr()) }.unwrap();
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(1, 0).0, [0, 255, 0, 0]);
+        assert_eq!(img.get_pixel(0, 1).0, [0, 0, 255, 0]);
+        assert_eq!(img.get_pixel(1, 1).0, [255, 255, 255, 255]);
+
+        let img = unsafe { surface_to_rgba(&surface, std::ptr::null()) }.unwrap();
+        assert_eq!(img.get_pixel(0, 1).0, [0, 0, 255, 128]);
+        assert_eq!(scaled(&img, 4).dimensions(), (4, 4));
+    }
+}
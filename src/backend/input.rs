@@ -0,0 +1,310 @@
+use crate::{
+    backend::{
+        events::{self, button_mask},
+        keys,
+        modifiers::Modifiers,
+    },
+    type_defs::{
+        SDL_BUTTON_LEFT, SDL_BUTTON_MIDDLE, SDL_BUTTON_RIGHT, SDL_BUTTON_X1, SDL_BUTTON_X2,
+        SDL_Event, SDL_keysym, SDLKey, SDLKey_SDLK_LAST,
+    },
+};
+
+// Linux's button codes, from input-event-codes.h.
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+const BTN_SIDE: u32 = 0x113;
+const BTN_EXTRA: u32 = 0x114;
+const BTN_FORWARD: u32 = 0x115;
+const BTN_BACK: u32 = 0x116;
+
+// How far a touchpad or similar has to scroll to count as one wheel click, in the units
+// wl_pointer and libinput use for smooth scrolling.
+const SMOOTH_SCROLL_PER_CLICK: f64 = 10.0;
+
+/// The SDL button a Linux button code is, if SDL has one for it.
+pub fn sdl_button(code: u32) -> Option<u8> {
+    match code {
+        BTN_LEFT => Some(SDL_BUTTON_LEFT),
+        BTN_MIDDLE => Some(SDL_BUTTON_MIDDLE),
+        BTN_RIGHT => Some(SDL_BUTTON_RIGHT),
+        BTN_SIDE | BTN_BACK => Some(SDL_BUTTON_X1),
+        BTN_EXTRA | BTN_FORWARD => Some(SDL_BUTTON_X2),
+        _ => None,
+    }
+}
+
+/// Everything SDL_GetKeyState, SDL_GetModState, SDL_GetMouseState and SDL_GetRelativeMouseState
+/// report. The backends keep it up to date as events come in, after the remap table has had its
+/// say.
+pub struct InputState {
+    // One byte per key like SDL's `Uint8 *`, since the game indexes it directly.
+    keys: [u8; SDLKey_SDLK_LAST as usize],
+    modifiers: Modifiers,
+    x: i32,
+    y: i32,
+    // Where the real pointer is, before rounding to a pixel.
+    pointer: (f64, f64),
+    // How far the mouse went since the last SDL_GetRelativeMouseState, bits of a pixel included.
+    relative: (f64, f64),
+    // Once the backend tells us how the mouse itself moved, without acceleration, that's what
+    // counts instead of where the pointer went.
+    raw_motion: bool,
+    buttons: u8,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self {
+            keys: [0; SDLKey_SDLK_LAST as usize],
+            modifiers: Modifiers::default(),
+            x: 0,
+            y: 0,
+            pointer: (0.0, 0.0),
+            relative: (0.0, 0.0),
+            raw_motion: false,
+            buttons: 0,
+        }
+    }
+}
+
+impl InputState {
+    /// SDL_GetKeyState. The array lives as long as the window does, and keeps changing as keys
+    /// are pressed, same as SDL's.
+    pub fn key_state(&mut self, numkeys: *mut i32) -> *mut u8 {
+        if !numkeys.is_null() {
+            unsafe { *numkeys = SDLKey_SDLK_LAST as i32 };
+        }
+        self.keys.as_mut_ptr()
+    }
+
+    /// SDL_GetMouseState. Either pointer can be null if the game doesn't care.
+    pub fn mouse_state(&self, x: *mut i32, y: *mut i32) -> u8 {
+        unsafe {
+            if !x.is_null() {
+                *x = self.x;
+            }
+            if !y.is_null() {
+                *y = self.y;
+            }
+        }
+        self.buttons
+    }
+
+    /// SDL_GetRelativeMouseState. Whole pixels only, the rest waits for next time.
+    pub fn relative_mouse_state(&mut self, x: *mut i32, y: *mut i32) -> u8 {
+        let (dx, dy) = (self.relative.0.trunc(), self.relative.1.trunc());
+        self.relative.0 -= dx;
+        self.relative.1 -= dy;
+        unsafe {
+            if !x.is_null() {
+                *x = dx as i32;
+            }
+            if !y.is_null() {
+                *y = dy as i32;
+            }
+        }
+        self.buttons
+    }
+
+    pub fn modifiers(&mut self) -> &mut Modifiers {
+        &mut self.modifiers
+    }
+
+    /// A key went down or up. Returns the keysym its event should carry, with the modifiers
+    /// already including the key itself.
+    pub fn key(&mut self, sym: SDLKey, pressed: bool, keycode: u32, unicode: u16) -> SDL_keysym {
+        self.modifiers.key(sym, pressed);
+        self.keys[sym as usize] = pressed as u8;
+        SDL_keysym {
+            scancode: keys::scancode(keycode),
+            sym,
+            mod_: self.modifiers.get(),
+            unicode,
+        }
+    }
+
+    /// A mouse button went down or up, wherever the pointer is now.
+    pub fn button(&mut self, button: u8, pressed: bool) -> SDL_Event {
+        if pressed {
+            self.buttons |= button_mask(button);
+        } else {
+            self.buttons &= !button_mask(button);
+        }
+        events::button_event(pressed, button, self.x as u16, self.y as u16)
+    }
+
+    /// Something other than the mouse, like a touchscreen or a gamepad, moved the pointer to
+    /// `x`, `y` in the window.
+    pub fn motion(&mut self, x: i32, y: i32) -> SDL_Event {
+        self.relative.0 += (x - self.x) as f64;
+        self.relative.1 += (y - self.y) as f64;
+        self.pointer = (x as f64, y as f64);
+        self.move_to(x, y)
+    }
+
+    /// The mouse moved the pointer to `x`, `y` in the window.
+    pub fn pointer_motion(&mut self, x: f64, y: f64) -> SDL_Event {
+        if !self.raw_motion {
+            self.relative.0 += x - self.pointer.0;
+            self.relative.1 += y - self.pointer.1;
+        }
+        self.pointer = (x, y);
+        self.move_to(x as i32, y as i32)
+    }
+
+    /// How far the mouse itself moved, without acceleration, for backends that know.
+    pub fn raw_motion(&mut self, dx: f64, dy: f64) {
+        self.raw_motion = true;
+        self.relative.0 += dx;
+        self.relative.1 += dy;
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> SDL_Event {
+        let (xrel, yrel) = (x - self.x, y - self.y);
+        self.x = x;
+        self.y = y;
+        let clamp = |rel: i32| rel.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        events::motion_event(self.buttons, x as u16, y as u16, clamp(xrel), clamp(yrel))
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn pointer_position(&self) -> (f64, f64) {
+        self.pointer
+    }
+
+    /// Moves the pointer without an event, like when it enters the window.
+    pub fn set_position(&mut self, x: f64, y: f64) {
+        self.pointer = (x, y);
+        self.x = x as i32;
+        self.y = y as i32;
+    }
+}
+
+/// Adds scrolling up into wheel clicks, which SDL 1.2 sends as a press and release of
+/// SDL_BUTTON_WHEELUP or SDL_BUTTON_WHEELDOWN.
+#[derive(Default)]
+pub struct Wheel {
+    // Scrolled but not a whole click yet, in 120ths of one.
+    partial: f64,
+    // What came in since the last frame(). Wheels say how far they turned in 120ths of a click,
+    // and if they did we ignore the smooth value that comes along with it.
+    v120: Option<f64>,
+    smooth: f64,
+}
+
+impl Wheel {
+    pub fn v120(&mut self, value: f64) {
+        *self.v120.get_or_insert(0.0) += value;
+    }
+
+    pub fn smooth(&mut self, value: f64) {
+        self.smooth += value;
+    }
+
+    /// Call once everything for one bit of scrolling is in. Returns how many clicks that made,
+    /// positive for down.
+    pub fn frame(&mut self) -> i32 {
+        let v120 = self
+            .v120
+            .take()
+            .unwrap_or(self.smooth * 120.0 / SMOOTH_SCROLL_PER_CLICK);
+        self.smooth = 0.0;
+        self.partial += v120;
+        let clicks = (self.partial / 120.0).trunc();
+        self.partial -= clicks * 120.0;
+        clicks as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_defs::{SDLKey_SDLK_LSHIFT, SDLKey_SDLK_a, SDLMod_KMOD_LSHIFT};
+
+    #[test]
+    fn key_state() {
+        let mut input = InputState::default();
+        input.key(SDLKey_SDLK_a, true, 38, 'a' as u16);
+        let keysym = input.key(SDLKey_SDLK_LSHIFT, true, 50, 0);
+        assert_eq!(keysym.mod_, SDLMod_KMOD_LSHIFT);
+
+        let mut numkeys = 0;
+        let keys = input.key_state(&mut numkeys);
+        let keys = unsafe { std::slice::from_raw_parts(keys, numkeys as usize) };
+        assert_eq!(keys[SDLKey_SDLK_a as usize], 1);
+        assert_eq!(keys[SDLKey_SDLK_a as usize + 1], 0);
+        assert_eq!(keys[SDLKey_SDLK_LSHIFT as usize], 1);
+    }
+
+    #[test]
+    fn mouse_state() {
+        let mut input = InputState::default();
+        input.set_position(10.0, 10.0);
+        input.button(1, true);
+        input.button(3, true);
+        input.button(1, false);
+        input.motion(15, 8);
+
+        let (mut x, mut y) = (0, 0);
+        assert_eq!(input.mouse_state(&mut x, &mut y), button_mask(3));
+        assert_eq!((x, y), (15, 8));
+        assert_eq!(
+            input.mouse_state(std::ptr::null_mut(), std::ptr::null_mut()),
+            4
+        );
+    }
+
+    #[test]
+    fn relative_mouse_state() {
+        let mut input = InputState::default();
+        input.set_position(10.0, 10.0);
+        input.pointer_motion(12.5, 9.75);
+        input.pointer_motion(13.25, 9.5);
+        let (mut x, mut y) = (0, 0);
+        input.relative_mouse_state(&mut x, &mut y);
+        assert_eq!((x, y), (3, 0));
+        input.pointer_motion(14.0, 9.0);
+        input.relative_mouse_state(&mut x, &mut y);
+        assert_eq!((x, y), (1, -1));
+        input.relative_mouse_state(&mut x, &mut y);
+        assert_eq!((x, y), (0, 0));
+
+        // With the mouse's own motion, the pointer doesn't count, but other things moving it do.
+        input.raw_motion(-4.5, 2.0);
+        input.pointer_motion(30.0, 30.0);
+        input.motion(31, 30);
+        input.relative_mouse_state(&mut x, &mut y);
+        assert_eq!((x, y), (-3, 2));
+    }
+
+    #[test]
+    fn buttons() {
+        assert_eq!(sdl_button(BTN_LEFT), Some(SDL_BUTTON_LEFT));
+        assert_eq!(sdl_button(BTN_MIDDLE), Some(SDL_BUTTON_MIDDLE));
+        assert_eq!(sdl_button(BTN_RIGHT), Some(SDL_BUTTON_RIGHT));
+        assert_eq!(sdl_button(0x120), None);
+    }
+
+    #[test]
+    fn wheel() {
+        let mut wheel = Wheel::default();
+        // A high-resolution wheel takes two events for a click, and the smooth value alongside
+        // it doesn't count twice.
+        wheel.v120(60.0);
+        wheel.smooth(5.0);
+        assert_eq!(wheel.frame(), 0);
+        wheel.v120(60.0);
+        wheel.smooth(5.0);
+        assert_eq!(wheel.frame(), 1);
+
+        wheel.smooth(-25.0);
+        assert_eq!(wheel.frame(), -2);
+        wheel.smooth(-5.0);
+        assert_eq!(wheel.frame(), -1);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use xkbcommon_rs::{
+    Context, Keymap, State,
+    keysym::keysym_to_lower,
+    xkb_context::ContextFlags,
+    xkb_keymap::{CompileFlags, RuleNames},
+};
+
+use crate::{
+    config::config,
+    type_defs::{SDLKey, SDLKey_SDLK_UNKNOWN},
+    xcb::sdl_key_from_keysym,
+};
+
+/// The SDL key a keycode produces right now, going by the layout and modifiers in `xkb`. Letters
+/// always come out lowercase, since that's the only kind SDL has.
+pub fn sdl_key(xkb: &State, keycode: u32) -> SDLKey {
+    match xkb.key_get_one_sym(keycode) {
+        Some(sym) => sdl_key_from_keysym(keysym_to_lower(&sym)),
+        None => SDLKey_SDLK_UNKNOWN,
+    }
+}
+
+/// What goes into `SDL_keysym.scancode`: the XKB keycode (evdev code plus 8), which is what SDL
+/// 1.2's X11 driver reported. Keycodes past 255 don't fit and come out as 0.
+pub fn scancode(keycode: u32) -> u8 {
+    u8::try_from(keycode).unwrap_or(0)
+}
+
+/// Remembers what each held key was pressed as, so the release matches even if shift or the
+/// layout changed in the meantime. Otherwise the first one stays down in SDL_GetKeyState forever.
+pub struct PressedKeys {
+    held: HashMap<u32, SDLKey>,
+    // With `physical_keys`, keys are looked up in this instead of the user's layout, so a game
+    // that wants WASD gets the keys where WASD would be on a US keyboard.
+    reference: Option<State>,
+}
+
+impl Default for PressedKeys {
+    fn default() -> Self {
+        Self::new(config().get_bool("physical_keys"))
+    }
+}
+
+impl PressedKeys {
+    pub fn new(physical: bool) -> Self {
+        let reference = if physical { reference_state() } else { None };
+        Self {
+            held: HashMap::new(),
+            reference,
+        }
+    }
+
+    pub fn press(&mut self, xkb: &State, keycode: u32) -> SDLKey {
+        let key = sdl_key(self.reference.as_ref().unwrap_or(xkb), keycode);
+        self.held.insert(keycode, key);
+        key
+    }
+
+    pub fn release(&mut self, xkb: &State, keycode: u32) -> SDLKey {
+        // Keys that were already down when we got focus never had a press.
+        self.held
+            .remove(&keycode)
+            .unwrap_or_else(|| sdl_key(self.reference.as_ref().unwrap_or(xkb), keycode))
+    }
+}
+
+// Plain US QWERTY with nothing held. Modifiers never make it in here, so unlike with the user's
+// own layout a key always comes out as what's printed on it, shift+1 included.
+fn reference_state() -> Option<State> {
+    let keymap = Keymap::new_from_names(
+        Context::new(ContextFlags::NO_FLAGS).ok()?,
+        Some(RuleNames {
+            rules: None,
+            model: None,
+            layout: Some("us".into()),
+            variant: Some(String::new()),
+            options: Some(String::new()),
+        }),
+        CompileFlags::NO_FLAGS,
+    );
+    match keymap {
+        Ok(keymap) => Some(State::new(keymap)),
+        Err(err) => {
+            println!("couldn't load the us keymap for physical_keys: {:?}", err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xkbcommon_rs::xkb_state::KeyDirection;
+
+    use super::*;
+    use crate::type_defs::{
+        SDLKey_SDLK_1, SDLKey_SDLK_AT, SDLKey_SDLK_EXCLAIM, SDLKey_SDLK_a, SDLKey_SDLK_f,
+        SDLKey_SDLK_q, SDLKey_SDLK_t, SDLKey_SDLK_w, SDLKey_SDLK_y, SDLKey_SDLK_z,
+    };
+
+    // evdev codes plus 8, like both backends hand them to xkb.
+    const KEY_1: u32 = 2 + 8;
+    const KEY_Q: u32 = 16 + 8;
+    const KEY_Y: u32 = 21 + 8;
+    const KEY_A: u32 = 30 + 8;
+    const KEY_K: u32 = 37 + 8;
+    const KEY_Z: u32 = 44 + 8;
+    const KEY_LEFTSHIFT: u32 = 42 + 8;
+    const KEY_RIGHTALT: u32 = 100 + 8;
+
+    fn state(layout: &str, variant: &str) -> State {
+        let keymap = Keymap::new_from_names(
+            Context::new(ContextFlags::NO_FLAGS).unwrap(),
+            Some(RuleNames {
+                rules: None,
+                model: None,
+                layout: Some(layout.into()),
+                variant: Some(variant.into()),
+                options: None,
+            }),
+            CompileFlags::NO_FLAGS,
+        )
+        .unwrap();
+        State::new(keymap)
+    }
+
+    #[test]
+    fn us() {
+        let mut xkb = state("us", "");
+        assert_eq!(sdl_key(&xkb, KEY_A), SDLKey_SDLK_a);
+        assert_eq!(sdl_key(&xkb, KEY_1), SDLKey_SDLK_1);
+
+        xkb.update_key(KEY_LEFTSHIFT, KeyDirection::Down);
+        assert_eq!(sdl_key(&xkb, KEY_A), SDLKey_SDLK_a);
+    }
+
+    #[test]
+    fn de() {
+        let mut xkb = state("de", "");
+        assert_eq!(sdl_key(&xkb, KEY_Y), SDLKey_SDLK_z);
+        assert_eq!(sdl_key(&xkb, KEY_Z), SDLKey_SDLK_y);
+
+        // AltGr+Q is @, but plain Q is just q.
+        assert_eq!(sdl_key(&xkb, KEY_Q), SDLKey_SDLK_q);
+        xkb.update_key(KEY_RIGHTALT, KeyDirection::Down);
+        assert_eq!(sdl_key(&xkb, KEY_Q), SDLKey_SDLK_AT);
+    }
+
+    #[test]
+    fn fr_azerty() {
+        let xkb = state("fr", "");
+        assert_eq!(sdl_key(&xkb, KEY_Q), SDLKey_SDLK_a);
+        assert_eq!(sdl_key(&xkb, KEY_A), SDLKey_SDLK_q);
+        assert_eq!(sdl_key(&xkb, KEY_Z), SDLKey_SDLK_w);
+    }
+
+    #[test]
+    fn dvorak() {
+        let xkb = state("us", "dvorak");
+        assert_eq!(sdl_key(&xkb, KEY_A), SDLKey_SDLK_a);
+        assert_eq!(sdl_key(&xkb, KEY_K), SDLKey_SDLK_t);
+        assert_eq!(sdl_key(&xkb, KEY_Y), SDLKey_SDLK_f);
+    }
+
+    #[test]
+    fn physical() {
+        let xkb = state("fr", "");
+        let mut pressed = PressedKeys::new(true);
+        assert_eq!(pressed.press(&xkb, KEY_Q), SDLKey_SDLK_q);
+        assert_eq!(pressed.press(&xkb, KEY_Z), SDLKey_SDLK_z);
+        assert_eq!(pressed.release(&xkb, KEY_Q), SDLKey_SDLK_q);
+        assert_eq!(PressedKeys::new(false).press(&xkb, KEY_Q), SDLKey_SDLK_a);
+
+        // Shift doesn't reach the reference layout.
+        let mut xkb = state("us", "");
+        xkb.update_key(KEY_LEFTSHIFT, KeyDirection::Down);
+        assert_eq!(pressed.press(&xkb, KEY_1), SDLKey_SDLK_1);
+        assert_eq!(
+            PressedKeys::new(false).press(&xkb, KEY_1),
+            SDLKey_SDLK_EXCLAIM
+        );
+    }
+
+    #[test]
+    fn scancodes() {
+        assert_eq!(scancode(KEY_A), 38);
+        assert_eq!(scancode(0x2c0 + 8), 0);
+    }
+
+    #[test]
+    fn release_matches_press() {
+        let mut xkb = state("us", "");
+        let mut pressed = PressedKeys::new(false);
+
+        xkb.update_key(KEY_LEFTSHIFT, KeyDirection::Down);
+        let down = pressed.press(&xkb, KEY_1);
+        assert_eq!(down, SDLKey_SDLK_EXCLAIM);
+        xkb.update_key(KEY_LEFTSHIFT, KeyDirection::Up);
+        assert_eq!(pressed.release(&xkb, KEY_1), down);
+        assert_eq!(pressed.release(&xkb, KEY_1), SDLKey_SDLK_1);
+    }
+}
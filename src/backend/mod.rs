@@ -1,13 +1,25 @@
+use std::os::fd::RawFd;
 #[cfg(feature = "drm")]
 use std::{
     ffi::{CStr, c_char, c_void},
     time::SystemTime,
 };
 
+use libc::{POLLIN, pollfd};
+
 use crate::{
-    backend::wayland::WaylandWindow,
+    backend::{
+        input::InputState,
+        queue::{EventFilter, Queue, SDL_ADDEVENT},
+        remap::Input,
+        repeat::KeyRepeat,
+        unicode::Unicode,
+        video::VideoModes,
+        wayland::WaylandWindow,
+    },
     egl::{EGL, EGLSurface, NativeDisplayType},
-    type_defs::{self, SDL_VideoInfo},
+    joystick::{Joysticks, emulation::Emulated},
+    type_defs::{self, SDL_ENABLE, SDL_EventType, SDL_FULLSCREEN, SDL_IGNORE, SDL_QUERY},
 };
 
 #[cfg(feature = "drm")]
@@ -15,29 +27,44 @@ mod drm;
 #[cfg(feature = "drm")]
 use {crate::backend::drm::DRMWindow, std::env::VarError};
 
+pub mod events;
+pub mod icon;
+mod input;
+mod keys;
+mod modifiers;
+pub mod queue;
+pub mod remap;
+mod repeat;
+mod touch;
+mod unicode;
+mod video;
 mod wayland;
 
-const VIDEO_INFO: SDL_VideoInfo = SDL_VideoInfo {
-    _bitfield_align_1: [],
-    _bitfield_1: unsafe { std::mem::transmute(255_u8) },
-    blit_fill: 255,
-    video_mem: 512000,
-    vfmt: unsafe { std::mem::transmute(0) },
-};
-
 pub trait Window {
     fn init(&mut self, _flags: u32) -> i32;
     fn quit(&mut self);
 
     fn get_error(&mut self) -> *const u8;
-    fn get_key_state(&mut self, numkeys: *mut i32) -> *mut u8;
-    fn get_mouse_state(&mut self, x: *mut i32, y: *mut i32) -> u8;
 
     fn egl(&self) -> &EGL;
     fn wait_for_egl(&mut self);
     fn egl_display(&self) -> NativeDisplayType;
     fn egl_surface(&self) -> EGLSurface;
 
+    fn video_modes(&mut self) -> &mut VideoModes;
+    /// Size of the display the game is on, if we know it yet.
+    fn display_size(&mut self) -> Option<(i32, i32)>;
+    /// Every size the game can set a video mode at on that display, largest first.
+    fn display_modes(&mut self) -> Vec<(i32, i32)>;
+    /// Size of the game's video mode, which is what mouse coordinates are in.
+    fn surface_size(&mut self) -> (i32, i32);
+    fn key_repeat(&mut self) -> &mut KeyRepeat;
+    fn input(&mut self) -> &mut InputState;
+    fn unicode(&mut self) -> &mut Unicode;
+    fn joysticks(&mut self) -> &mut Joysticks;
+    /// Events waiting for SDL_PollEvent.
+    fn events(&mut self) -> &mut Queue;
+
     fn gl_get_attribute(&mut self, attr: type_defs::SDL_GLattr, value: *mut i32) -> i32 {
         // unsafe { *value = self.gl_attrs[attr as usize] };
         return 0;
@@ -76,7 +103,83 @@ pub trait Window {
         }
     }
 
-    fn poll_event(&mut self, event: *mut type_defs::SDL_Event) -> i32;
+    /// Reads whatever input came in and puts it in the queue, without blocking.
+    fn pump_events(&mut self);
+    /// What the backend's input comes in on, for SDL_WaitEvent to sleep on.
+    fn input_fds(&mut self) -> Vec<RawFd>;
+    /// Gets the backend ready for SDL_WaitEvent to sleep on its fds. False if there's input to
+    /// pump already, so it shouldn't sleep at all.
+    fn prepare_wait(&mut self) -> bool {
+        true
+    }
+    /// SDL_WaitEvent woke up, for whatever reason.
+    fn finish_wait(&mut self) {}
+
+    /// Takes the oldest event off the queue, or with a null `event` just says if there is one.
+    fn poll_event(&mut self, event: *mut type_defs::SDL_Event) -> i32 {
+        if event.is_null() {
+            return !self.events().is_empty() as i32;
+        }
+        match self.events().pop() {
+            Some(ev) => {
+                unsafe { *event = ev };
+                1
+            }
+            None => 0,
+        }
+    }
+    fn event_state(&mut self, type_: u8, state: i32) -> u8 {
+        self.events().event_state(type_, state)
+    }
+    fn get_event_filter(&mut self) -> EventFilter {
+        self.events().filter()
+    }
+    fn peep_events(
+        &mut self,
+        events: *mut type_defs::SDL_Event,
+        numevents: i32,
+        action: i32,
+        mask: u32,
+    ) -> i32 {
+        self.events().peep(events, numevents, action, mask)
+    }
+    fn push_event(&mut self, event: *mut type_defs::SDL_Event) -> i32 {
+        match self.events().peep(event, 1, SDL_ADDEVENT, 0) {
+            1 => 0,
+            _ => -1,
+        }
+    }
+    fn set_event_filter(&mut self, filter: EventFilter) {
+        self.events().set_filter(filter)
+    }
+    /// What SDL_WaitEvent should sleep on and for how long at most, in milliseconds or -1 for as
+    /// long as it takes.
+    fn wait_for(&mut self) -> (Vec<pollfd>, i32) {
+        if !self.prepare_wait() {
+            return (vec![], 0);
+        }
+        self.events().clear_wake();
+        let mut fds = self.input_fds();
+        fds.extend(self.joysticks().fds());
+        fds.extend(self.events().wake_fd());
+        let fds = fds
+            .into_iter()
+            .map(|fd| pollfd {
+                fd,
+                events: POLLIN,
+                revents: 0,
+            })
+            .collect();
+        let timeout = [self.key_repeat().next(), self.joysticks().timeout()]
+            .into_iter()
+            .flatten()
+            .min()
+            // Rounded up, so we don't wake up just before it's time and then spin.
+            .map_or(-1, |timeout| {
+                timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+            });
+        (fds, timeout)
+    }
     fn set_video_mode(
         &mut self,
         width: i32,
@@ -87,6 +190,7 @@ pub trait Window {
     fn show_cursor(&mut self, toggle: i32) -> i32;
     fn warp_mouse(&mut self, x: u16, y: u16);
     fn wm_set_caption(&mut self, title: &str, icon: &str);
+    fn wm_set_icon(&mut self, icon: image::RgbaImage) {}
 
     fn delay(&mut self, ms: u32) {
         let time = SystemTime::now();
@@ -96,38 +200,151 @@ pub trait Window {
     }
 
     fn enable_key_repeat(&mut self, delay: i32, interval: i32) -> i32 {
-        return 0;
+        self.key_repeat().enable(delay, interval)
+    }
+    fn enable_unicode(&mut self, enable: i32) -> i32 {
+        self.unicode().enable(enable)
+    }
+    fn get_key_state(&mut self, numkeys: *mut i32) -> *mut u8 {
+        self.input().key_state(numkeys)
+    }
+    fn get_mod_state(&mut self) -> type_defs::SDLMod {
+        self.input().modifiers().get()
+    }
+    fn set_mod_state(&mut self, modstate: type_defs::SDLMod) {
+        self.input().modifiers().set(modstate);
+    }
+    fn get_mouse_state(&mut self, x: *mut i32, y: *mut i32) -> u8 {
+        self.input().mouse_state(x, y)
+    }
+    fn get_relative_mouse_state(&mut self, x: *mut i32, y: *mut i32) -> u8 {
+        self.input().relative_mouse_state(x, y)
     }
 
     fn get_video_info(&mut self) -> *mut type_defs::SDL_VideoInfo {
-        // the game never actually "mutates" VideoInfo so to speak so this is fine.
-        #[allow(const_item_mutation)]
-        return &mut VIDEO_INFO;
+        let size = self.display_size();
+        self.video_modes().video_info(size)
+    }
+    fn list_modes(
+        &mut self,
+        format: *mut type_defs::SDL_PixelFormat,
+        _flags: u32,
+    ) -> *mut *mut type_defs::SDL_Rect {
+        // Real drivers answer "anything goes" (-1) for windowed modes, but then the game has no
+        // idea how big it can make its window. Full screen is scaled to whatever the display is
+        // in anyways, so both get every mode that fits on it, and -1 only until we know the
+        // display.
+        let modes = self.display_modes();
+        self.video_modes().list_modes(&modes)
+    }
+    fn video_mode_ok(&mut self, width: i32, height: i32, bpp: i32, flags: u32) -> i32 {
+        let modes = self.display_modes();
+        let ok = if flags & SDL_FULLSCREEN == SDL_FULLSCREEN {
+            modes.is_empty() || modes.contains(&(width, height))
+        } else {
+            modes
+                .first()
+                .is_none_or(|&(w, h)| width <= w && height <= h)
+        };
+        if !ok {
+            return 0;
+        }
+        // Everything ends up in a 32-bit GL surface anyways.
+        match bpp {
+            15 | 16 | 24 | 32 => bpp,
+            _ => 32,
+        }
+    }
+    fn joystick_close(&mut self, joystick: *mut type_defs::SDL_Joystick) {
+        self.joysticks().close(joystick)
     }
+    /// Same as SDL 1.2, it's SDL_EventState for every joystick event type at once.
     fn joystick_event_state(&mut self, state: i32) -> i32 {
-        return 0;
-        // unimplemented!("joystick_event_state");
+        let types = [
+            SDL_EventType::SDL_JOYAXISMOTION as u8,
+            SDL_EventType::SDL_JOYBALLMOTION as u8,
+            SDL_EventType::SDL_JOYHATMOTION as u8,
+            SDL_EventType::SDL_JOYBUTTONDOWN as u8,
+            SDL_EventType::SDL_JOYBUTTONUP as u8,
+        ];
+        if state == SDL_QUERY {
+            let enabled = types
+                .iter()
+                .any(|&type_| self.events().event_state(type_, SDL_QUERY) == SDL_ENABLE as u8);
+            return if enabled { SDL_ENABLE } else { SDL_IGNORE };
+        }
+        for type_ in types {
+            self.events().event_state(type_, state);
+        }
+        state
     }
     fn joystick_get_axis(&mut self, joystick: *mut type_defs::SDL_Joystick, axis: i32) -> i16 {
-        unimplemented!("joystick_get_axis");
+        self.joysticks().axis(joystick, axis)
+    }
+    fn joystick_get_ball(
+        &mut self,
+        joystick: *mut type_defs::SDL_Joystick,
+        ball: i32,
+        dx: *mut i32,
+        dy: *mut i32,
+    ) -> i32 {
+        self.joysticks().ball(joystick, ball, dx, dy)
     }
     fn joystick_get_button(&mut self, joystick: *mut type_defs::SDL_Joystick, button: i32) -> u8 {
-        unimplemented!("joystick_get_button");
+        self.joysticks().button(joystick, button)
+    }
+    fn joystick_get_hat(&mut self, joystick: *mut type_defs::SDL_Joystick, hat: i32) -> u8 {
+        self.joysticks().hat(joystick, hat)
+    }
+    fn joystick_index(&mut self, joystick: *mut type_defs::SDL_Joystick) -> i32 {
+        self.joysticks().index(joystick)
     }
     fn joystick_name(&mut self, index: i32) -> *const c_char {
-        unimplemented!("joystick_name");
+        self.joysticks().name(index)
     }
     fn joystick_num_axes(&mut self, joystick: *mut type_defs::SDL_Joystick) -> i32 {
-        unimplemented!("joystick_num_axes");
+        self.joysticks().num_axes(joystick)
+    }
+    fn joystick_num_balls(&mut self, joystick: *mut type_defs::SDL_Joystick) -> i32 {
+        self.joysticks().num_balls(joystick)
     }
     fn joystick_num_buttons(&mut self, joystick: *mut type_defs::SDL_Joystick) -> i32 {
-        unimplemented!("joystick_num_buttons");
+        self.joysticks().num_buttons(joystick)
+    }
+    fn joystick_num_hats(&mut self, joystick: *mut type_defs::SDL_Joystick) -> i32 {
+        self.joysticks().num_hats(joystick)
     }
     fn joystick_open(&mut self, index: i32) -> *mut type_defs::SDL_Joystick {
-        unimplemented!("joystick_open");
+        self.joysticks().open(index)
+    }
+    fn joystick_opened(&mut self, index: i32) -> i32 {
+        self.joysticks().opened(index)
+    }
+    fn joystick_update(&mut self) {
+        let changed = self.joysticks().update();
+        self.events().extend(changed);
+        for emulated in self.joysticks().emulated() {
+            let event = match emulated {
+                Emulated::Input(Input::Key(sym), pressed) => {
+                    let keysym = self.input().key(sym, pressed, 0, 0);
+                    events::key_event(pressed, keysym)
+                }
+                Emulated::Input(Input::Button(button), pressed) => {
+                    self.input().button(button, pressed)
+                }
+                Emulated::Motion(dx, dy) => {
+                    let (x, y) = self.input().position();
+                    let (w, h) = self.surface_size();
+                    // Not clamp(), that panics if the game set a 0x0 mode.
+                    self.input()
+                        .motion((x + dx).min(w - 1).max(0), (y + dy).min(h - 1).max(0))
+                }
+            };
+            self.events().push(event);
+        }
     }
     fn num_joysticks(&mut self) -> i32 {
-        return 0;
+        self.joysticks().count()
     }
 }
 
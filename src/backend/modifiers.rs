@@ -0,0 +1,208 @@
+use xkbcommon_rs::{
+    State,
+    xkb_state::{ModName, StateComponent},
+};
+
+use crate::type_defs::{
+    SDLKey, SDLKey_SDLK_CAPSLOCK, SDLKey_SDLK_LALT, SDLKey_SDLK_LCTRL, SDLKey_SDLK_LMETA,
+    SDLKey_SDLK_LSHIFT, SDLKey_SDLK_MODE, SDLKey_SDLK_NUMLOCK, SDLKey_SDLK_RALT, SDLKey_SDLK_RCTRL,
+    SDLKey_SDLK_RMETA, SDLKey_SDLK_RSHIFT, SDLMod, SDLMod_KMOD_CAPS, SDLMod_KMOD_LALT,
+    SDLMod_KMOD_LCTRL, SDLMod_KMOD_LMETA, SDLMod_KMOD_LSHIFT, SDLMod_KMOD_MODE, SDLMod_KMOD_NONE,
+    SDLMod_KMOD_NUM, SDLMod_KMOD_RALT, SDLMod_KMOD_RCTRL, SDLMod_KMOD_RMETA, SDLMod_KMOD_RSHIFT,
+};
+
+// Which side of a modifier is held is something only the key events tell us, xkb just knows
+// "shift".
+const MODIFIER_KEYS: [(SDLKey, SDLMod); 9] = [
+    (SDLKey_SDLK_LSHIFT, SDLMod_KMOD_LSHIFT),
+    (SDLKey_SDLK_RSHIFT, SDLMod_KMOD_RSHIFT),
+    (SDLKey_SDLK_LCTRL, SDLMod_KMOD_LCTRL),
+    (SDLKey_SDLK_RCTRL, SDLMod_KMOD_RCTRL),
+    (SDLKey_SDLK_LALT, SDLMod_KMOD_LALT),
+    (SDLKey_SDLK_RALT, SDLMod_KMOD_RALT),
+    (SDLKey_SDLK_LMETA, SDLMod_KMOD_LMETA),
+    (SDLKey_SDLK_RMETA, SDLMod_KMOD_RMETA),
+    (SDLKey_SDLK_MODE, SDLMod_KMOD_MODE),
+];
+
+const XKB_MODIFIERS: [(ModName, SDLMod, SDLMod); 3] = [
+    (ModName::SHIFT, SDLMod_KMOD_LSHIFT, SDLMod_KMOD_RSHIFT),
+    (ModName::CTRL, SDLMod_KMOD_LCTRL, SDLMod_KMOD_RCTRL),
+    (ModName::ALT, SDLMod_KMOD_LALT, SDLMod_KMOD_RALT),
+];
+
+/// What SDL_GetModState returns and what goes into every keysym's `mod_`.
+pub struct Modifiers {
+    state: SDLMod,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self {
+            state: SDLMod_KMOD_NONE,
+        }
+    }
+}
+
+impl Modifiers {
+    pub fn get(&self) -> SDLMod {
+        self.state
+    }
+
+    /// SDL_SetModState. Doesn't touch the actual keyboard, it just changes what we report until
+    /// the next key or xkb update says otherwise.
+    pub fn set(&mut self, state: SDLMod) {
+        self.state = state;
+    }
+
+    /// Called for every key before its event goes out, so that pressing shift already comes with
+    /// KMOD_LSHIFT like in SDL.
+    pub fn key(&mut self, sym: SDLKey, pressed: bool) {
+        // The lock keys toggle on press, xkb gets the final say in sync().
+        if sym == SDLKey_SDLK_CAPSLOCK {
+            if pressed {
+                self.state ^= SDLMod_KMOD_CAPS;
+            }
+        } else if sym == SDLKey_SDLK_NUMLOCK {
+            if pressed {
+                self.state ^= SDLMod_KMOD_NUM;
+            }
+        } else if let Some(&(_, bit)) = MODIFIER_KEYS.iter().find(|(key, _)| *key == sym) {
+            if pressed {
+                self.state |= bit;
+            } else {
+                self.state &= !bit;
+            }
+        }
+    }
+
+    /// Brings us in line with xkb, for the lock keys and for modifiers that changed without us
+    /// seeing the key, like when they're held while the window gets focus.
+    pub fn sync(&mut self, xkb: &State) {
+        let active = |name: &ModName, component: StateComponent| {
+            xkb.mod_name_is_active(name, component).unwrap_or(false)
+        };
+
+        for (name, left, right) in &XKB_MODIFIERS {
+            if !active(name, StateComponent::MODS_EFFECTIVE) {
+                self.state &= !(left | right);
+            } else if self.state & (left | right) == 0 {
+                self.state |= left;
+            }
+        }
+        for (name, bit) in [
+            (ModName::CAPS, SDLMod_KMOD_CAPS),
+            (ModName::NUM, SDLMod_KMOD_NUM),
+        ] {
+            if active(&name, StateComponent::MODS_LOCKED) {
+                self.state |= bit;
+            } else {
+                self.state &= !bit;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xkbcommon_rs::{
+        Context, Keymap,
+        xkb_context::ContextFlags,
+        xkb_keymap::{CompileFlags, RuleNames},
+        xkb_state::KeyDirection,
+    };
+
+    use super::*;
+
+    // evdev codes plus 8.
+    const KEY_LEFTSHIFT: u32 = 42 + 8;
+    const KEY_CAPSLOCK: u32 = 58 + 8;
+    const KEY_NUMLOCK: u32 = 69 + 8;
+
+    fn us() -> State {
+        let keymap = Keymap::new_from_names(
+            Context::new(ContextFlags::NO_FLAGS).unwrap(),
+            Some(RuleNames {
+                rules: None,
+                model: None,
+                layout: Some("us".into()),
+                variant: None,
+                options: None,
+            }),
+            CompileFlags::NO_FLAGS,
+        )
+        .unwrap();
+        State::new(keymap)
+    }
+
+    fn tap(xkb: &mut State, keycode: u32) {
+        xkb.update_key(keycode, KeyDirection::Down);
+        xkb.update_key(keycode, KeyDirection::Up);
+    }
+
+    #[test]
+    fn sides() {
+        let mut modifiers = Modifiers::default();
+        modifiers.key(SDLKey_SDLK_LSHIFT, true);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_LSHIFT);
+        modifiers.key(SDLKey_SDLK_RSHIFT, true);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_LSHIFT | SDLMod_KMOD_RSHIFT);
+        modifiers.key(SDLKey_SDLK_LSHIFT, false);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_RSHIFT);
+        modifiers.key(SDLKey_SDLK_MODE, true);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_RSHIFT | SDLMod_KMOD_MODE);
+    }
+
+    #[test]
+    fn sync() {
+        let mut xkb = us();
+        let mut modifiers = Modifiers::default();
+
+        // Shift held from before we had focus, so we never saw which one.
+        xkb.update_key(KEY_LEFTSHIFT, KeyDirection::Down);
+        modifiers.sync(&xkb);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_LSHIFT);
+        // Right shift we saw, and xkb only knows it's "shift".
+        modifiers.set(SDLMod_KMOD_RSHIFT);
+        modifiers.sync(&xkb);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_RSHIFT);
+        xkb.update_key(KEY_LEFTSHIFT, KeyDirection::Up);
+        modifiers.sync(&xkb);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_NONE);
+    }
+
+    #[test]
+    fn locks() {
+        let mut xkb = us();
+        let mut modifiers = Modifiers::default();
+
+        modifiers.key(SDLKey_SDLK_CAPSLOCK, true);
+        modifiers.key(SDLKey_SDLK_CAPSLOCK, false);
+        tap(&mut xkb, KEY_CAPSLOCK);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_CAPS);
+        modifiers.sync(&xkb);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_CAPS);
+
+        tap(&mut xkb, KEY_NUMLOCK);
+        modifiers.sync(&xkb);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_CAPS | SDLMod_KMOD_NUM);
+
+        // xkb wins over what we guessed from the keys.
+        tap(&mut xkb, KEY_CAPSLOCK);
+        modifiers.key(SDLKey_SDLK_CAPSLOCK, true);
+        modifiers.key(SDLKey_SDLK_CAPSLOCK, true);
+        modifiers.sync(&xkb);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_NUM);
+    }
+
+    #[test]
+    fn set() {
+        let mut modifiers = Modifiers::default();
+        modifiers.key(SDLKey_SDLK_LCTRL, true);
+        modifiers.set(SDLMod_KMOD_CAPS | SDLMod_KMOD_RALT);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_CAPS | SDLMod_KMOD_RALT);
+        // It sticks until a key says otherwise.
+        modifiers.key(SDLKey_SDLK_RALT, false);
+        assert_eq!(modifiers.get(), SDLMod_KMOD_CAPS);
+    }
+}
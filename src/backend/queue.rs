@@ -0,0 +1,346 @@
+use std::{
+    collections::VecDeque,
+    ffi::c_int,
+    fs::File,
+    io::{Read, Write},
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    time::Instant,
+};
+
+use libc::{EFD_CLOEXEC, EFD_NONBLOCK};
+
+use crate::type_defs::{SDL_ENABLE, SDL_Event, SDL_EventType, SDL_IGNORE, SDL_QUERY};
+
+// SDL_MAXEVENTS. SDL 1.2 keeps them in a ring buffer that's full with one slot still free.
+const MAX_EVENTS: usize = 128;
+const NUM_EVENTS: usize = SDL_EventType::SDL_NUMEVENTS as usize;
+// SDL_ALLEVENTS, for SDL_EventState.
+const ALL_EVENTS: u8 = 0xff;
+
+// SDL_eventaction
+pub const SDL_ADDEVENT: i32 = 0;
+pub const SDL_PEEKEVENT: i32 = 1;
+pub const SDL_GETEVENT: i32 = 2;
+
+// SDL_EventFilter
+pub type EventFilter = Option<unsafe extern "C" fn(event: *const SDL_Event) -> c_int>;
+
+/// Every event on its way to the game, oldest first, whichever input it came from.
+pub struct Queue {
+    events: VecDeque<(Instant, SDL_Event)>,
+    // So a game that stopped polling doesn't get a line for every event it misses.
+    overflowing: bool,
+    // SDL_EventState, by event type.
+    ignored: [bool; NUM_EVENTS],
+    // SDL_SetEventFilter. It's the game's code and could call back into us, so it runs on
+    // what's in `unfiltered` once the window's unlocked.
+    filter: EventFilter,
+    unfiltered: Vec<SDL_Event>,
+    // An eventfd that wakes SDL_WaitEvent up when another thread pushes an event.
+    wake: Option<File>,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        let fd = unsafe { libc::eventfd(0, EFD_NONBLOCK | EFD_CLOEXEC) };
+        let wake = if fd < 0 {
+            println!(
+                "couldn't create an eventfd, SDL_WaitEvent won't see events from other threads right away"
+            );
+            None
+        } else {
+            Some(unsafe { File::from_raw_fd(fd) })
+        };
+        Self {
+            events: VecDeque::with_capacity(MAX_EVENTS),
+            overflowing: false,
+            ignored: [false; NUM_EVENTS],
+            filter: None,
+            unfiltered: vec![],
+            wake,
+        }
+    }
+}
+
+impl Queue {
+    /// An event from the backend, which goes through SDL_EventState and the filter first.
+    pub fn push(&mut self, event: SDL_Event) {
+        if self.ignored(unsafe { event.type_ }) {
+            return;
+        }
+        if self.filter.is_some() {
+            self.unfiltered.push(event);
+        } else {
+            self.add(event);
+        }
+    }
+
+    pub fn extend(&mut self, events: impl IntoIterator<Item = SDL_Event>) {
+        for event in events {
+            self.push(event);
+        }
+    }
+
+    /// Adds an event to the end as is, unless the queue is full, in which case it's dropped
+    /// like SDL 1.2 does. Returns whether it made it in.
+    pub fn add(&mut self, event: SDL_Event) -> bool {
+        if self.events.len() >= MAX_EVENTS - 1 {
+            if !self.overflowing {
+                let waited = self.events.front().map(|(at, _)| at.elapsed());
+                println!(
+                    "event queue full, dropping events until the game catches up (the oldest has waited {:?})",
+                    waited.unwrap_or_default()
+                );
+                self.overflowing = true;
+            }
+            return false;
+        }
+        self.events.push_back((Instant::now(), event));
+        true
+    }
+
+    /// The oldest event, if there is one.
+    pub fn pop(&mut self) -> Option<SDL_Event> {
+        let (_, event) = self.events.pop_front()?;
+        self.overflowing = false;
+        Some(event)
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// SDL_PeepEvents. `events` can be null to just count them.
+    pub fn peep(&mut self, events: *mut SDL_Event, numevents: i32, action: i32, mask: u32) -> i32 {
+        let Ok(numevents) = usize::try_from(numevents) else {
+            return -1;
+        };
+        if action == SDL_ADDEVENT {
+            if events.is_null() {
+                return -1;
+            }
+            let events = unsafe { std::slice::from_raw_parts(events, numevents) };
+            let added = events.iter().take_while(|&&event| self.add(event)).count();
+            self.wake();
+            return added as i32;
+        }
+        if action != SDL_PEEKEVENT && action != SDL_GETEVENT {
+            println!("SDL_PeepEvents: unknown action {}", action);
+            return -1;
+        }
+
+        let mut found = 0;
+        let mut i = 0;
+        while i < self.events.len() && found < numevents {
+            let event = self.events[i].1;
+            if !matches(mask, unsafe { event.type_ }) {
+                i += 1;
+                continue;
+            }
+            if !events.is_null() {
+                unsafe { *events.add(found) = event };
+            }
+            found += 1;
+            if action == SDL_GETEVENT {
+                self.events.remove(i);
+                self.overflowing = false;
+            } else {
+                i += 1;
+            }
+        }
+        found as i32
+    }
+
+    /// SDL_EventState. Returns what the state was before, or with SDL_ALLEVENTS whether any type
+    /// was enabled.
+    pub fn event_state(&mut self, type_: u8, state: i32) -> u8 {
+        if state != SDL_QUERY && state != SDL_IGNORE && state != SDL_ENABLE {
+            println!("SDL_EventState: unknown state {}", state);
+        }
+        let types = if type_ == ALL_EVENTS {
+            0..NUM_EVENTS
+        } else if (type_ as usize) < NUM_EVENTS {
+            type_ as usize..type_ as usize + 1
+        } else {
+            return SDL_IGNORE as u8;
+        };
+        let before = types.clone().any(|type_| !self.ignored[type_]);
+        if state == SDL_IGNORE || state == SDL_ENABLE {
+            for type_ in types {
+                self.ignored[type_] = state == SDL_IGNORE;
+            }
+        }
+        // Ignoring a type throws away the ones already waiting too.
+        if state == SDL_IGNORE {
+            let ignored = self.ignored;
+            self.events
+                .retain(|(_, event)| !ignored[unsafe { event.type_ } as usize]);
+        }
+        if before {
+            SDL_ENABLE as u8
+        } else {
+            SDL_IGNORE as u8
+        }
+    }
+
+    pub fn filter(&self) -> EventFilter {
+        self.filter
+    }
+
+    /// SDL_SetEventFilter. Whatever's in the queue already stays.
+    pub fn set_filter(&mut self, filter: EventFilter) {
+        self.filter = filter;
+        if filter.is_none() {
+            for event in self.take_unfiltered() {
+                self.add(event);
+            }
+        }
+    }
+
+    /// The backend's events since the last time, for the filter to have a look at before they
+    /// go in with `add`.
+    pub fn take_unfiltered(&mut self) -> Vec<SDL_Event> {
+        std::mem::take(&mut self.unfiltered)
+    }
+
+    pub fn wake_fd(&self) -> Option<RawFd> {
+        self.wake.as_ref().map(|wake| wake.as_raw_fd())
+    }
+
+    /// Lets SDL_WaitEvent know there's something new, if it's waiting on another thread.
+    pub fn wake(&self) {
+        if let Some(mut wake) = self.wake.as_ref() {
+            let _ = wake.write(&1u64.to_ne_bytes());
+        }
+    }
+
+    /// Call before waiting, so only wakes after it count.
+    pub fn clear_wake(&self) {
+        if let Some(mut wake) = self.wake.as_ref() {
+            let _ = wake.read(&mut [0; 8]);
+        }
+    }
+
+    fn ignored(&self, type_: u8) -> bool {
+        self.ignored.get(type_ as usize).copied().unwrap_or(false)
+    }
+}
+
+// SDL_EVENTMASK
+fn matches(mask: u32, type_: u8) -> bool {
+    1u32.checked_shl(type_ as u32)
+        .is_some_and(|bit| mask & bit != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::events;
+
+    fn types(queue: &mut Queue) -> Vec<u8> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|event| unsafe { event.type_ })
+            .collect()
+    }
+
+    #[test]
+    fn order() {
+        let mut queue = Queue::default();
+        queue.push(events::button_event(true, 1, 0, 0));
+        queue.extend([
+            events::motion_event(0, 5, 5, 5, 5),
+            events::button_event(false, 1, 5, 5),
+        ]);
+        assert_eq!(
+            types(&mut queue),
+            [
+                SDL_EventType::SDL_MOUSEBUTTONDOWN as u8,
+                SDL_EventType::SDL_MOUSEMOTION as u8,
+                SDL_EventType::SDL_MOUSEBUTTONUP as u8
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full() {
+        let mut queue = Queue::default();
+        for x in 0..MAX_EVENTS {
+            queue.push(events::motion_event(0, x as u16, 0, 1, 0));
+        }
+        assert_eq!(queue.len(), MAX_EVENTS - 1);
+        // The newest ones are the ones that didn't make it.
+        let last = (0..MAX_EVENTS).filter_map(|_| queue.pop()).last().unwrap();
+        assert_eq!(unsafe { last.motion.x }, MAX_EVENTS as u16 - 2);
+        assert!(queue.add(events::motion_event(0, 0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn peep() {
+        let mut queue = Queue::default();
+        let motion = SDL_EventType::SDL_MOUSEMOTION as u8;
+        let button = SDL_EventType::SDL_MOUSEBUTTONDOWN as u8;
+        queue.push(events::motion_event(0, 1, 1, 1, 1));
+        queue.push(events::button_event(true, 1, 1, 1));
+        queue.push(events::motion_event(0, 2, 2, 1, 1));
+
+        let mut found = [events::motion_event(0, 0, 0, 0, 0); 4];
+        let mask = 1 << motion;
+        assert_eq!(queue.peep(found.as_mut_ptr(), 4, SDL_PEEKEVENT, mask), 2);
+        assert_eq!(unsafe { found[1].motion.x }, 2);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peep(std::ptr::null_mut(), 1, SDL_GETEVENT, mask), 1);
+        assert_eq!(types(&mut queue), [button, motion]);
+
+        assert_eq!(queue.peep(found.as_mut_ptr(), 2, SDL_ADDEVENT, 0), 2);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn event_state() {
+        let mut queue = Queue::default();
+        let motion = SDL_EventType::SDL_MOUSEMOTION as u8;
+        let button = SDL_EventType::SDL_MOUSEBUTTONDOWN as u8;
+        queue.push(events::motion_event(0, 1, 1, 1, 1));
+        queue.push(events::button_event(true, 1, 1, 1));
+
+        assert_eq!(queue.event_state(motion, SDL_IGNORE), SDL_ENABLE as u8);
+        assert_eq!(queue.event_state(motion, SDL_QUERY), SDL_IGNORE as u8);
+        queue.push(events::motion_event(0, 2, 2, 1, 1));
+        assert_eq!(types(&mut queue), [button]);
+
+        // SDL_PushEvent doesn't care.
+        queue.add(events::motion_event(0, 2, 2, 1, 1));
+        assert_eq!(types(&mut queue), [motion]);
+
+        assert_eq!(queue.event_state(ALL_EVENTS, SDL_ENABLE), SDL_ENABLE as u8);
+        assert_eq!(queue.event_state(motion, SDL_QUERY), SDL_ENABLE as u8);
+    }
+
+    #[test]
+    fn filter() {
+        unsafe extern "C" fn no_motion(event: *const SDL_Event) -> c_int {
+            unsafe { (*event).type_ != SDL_EventType::SDL_MOUSEMOTION as u8 }.into()
+        }
+        let mut queue = Queue::default();
+        queue.set_filter(Some(no_motion));
+        queue.push(events::motion_event(0, 1, 1, 1, 1));
+        queue.push(events::button_event(true, 1, 1, 1));
+        assert!(queue.is_empty());
+
+        let filter = queue.filter().unwrap();
+        for event in queue.take_unfiltered() {
+            if unsafe { filter(&event) } != 0 {
+                queue.add(event);
+            }
+        }
+        assert_eq!(
+            types(&mut queue),
+            [SDL_EventType::SDL_MOUSEBUTTONDOWN as u8]
+        );
+    }
+}
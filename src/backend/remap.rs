@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use crate::{
+    config::config,
+    type_defs::{
+        SDLKey, SDLMod, SDLMod_KMOD_LALT, SDLMod_KMOD_LCTRL, SDLMod_KMOD_LMETA, SDLMod_KMOD_LSHIFT,
+        SDLMod_KMOD_RALT, SDLMod_KMOD_RCTRL, SDLMod_KMOD_RMETA, SDLMod_KMOD_RSHIFT,
+    },
+    xcb::key_from_name,
+};
+
+const MODIFIER_NAMES: [(&str, SDLMod); 4] = [
+    ("ctrl", SDLMod_KMOD_LCTRL | SDLMod_KMOD_RCTRL),
+    ("shift", SDLMod_KMOD_LSHIFT | SDLMod_KMOD_RSHIFT),
+    ("alt", SDLMod_KMOD_LALT | SDLMod_KMOD_RALT),
+    ("meta", SDLMod_KMOD_LMETA | SDLMod_KMOD_RMETA),
+];
+
+/// Something the player can press, as SDL would report it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Input {
+    Key(SDLKey),
+    Button(u8),
+}
+
+impl Input {
+    /// SDL key names like `left shift` or `[5]`, and `mouse1` to `mouse8` for buttons.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let button = name
+            .strip_prefix("mouse")
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| (1..=8).contains(n));
+        match button {
+            Some(button) => Some(Self::Button(button)),
+            None => key_from_name(name).map(Self::Key),
+        }
+    }
+}
+
+struct Rule {
+    from: Input,
+    // Each entry is one modifier that has to be held, either side of it.
+    modifiers: Vec<SDLMod>,
+    to: Input,
+}
+
+impl Rule {
+    // `ctrl+q = escape`, `mouse3 = space`, `ctrl++ = mouse1`.
+    fn parse(from: &str, to: &str) -> Option<Self> {
+        let mut from = from.trim();
+        let mut modifiers = vec![];
+        while let Some((prefix, rest)) = from.split_once('+') {
+            let Some(&(_, bits)) = MODIFIER_NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(prefix.trim()))
+            else {
+                break;
+            };
+            if rest.is_empty() {
+                break;
+            }
+            modifiers.push(bits);
+            from = rest;
+        }
+        Some(Self {
+            from: Input::parse(from)?,
+            modifiers,
+            to: Input::parse(to)?,
+        })
+    }
+
+    fn matches(&self, input: Input, mods: SDLMod) -> bool {
+        self.from == input && self.modifiers.iter().all(|bits| mods & bits != 0)
+    }
+}
+
+/// The `[remap]` table: keys and mouse buttons that should come out as some other key or button.
+/// Everything after this, the events and SDL_GetKeyState and SDL_GetMouseState, only ever sees
+/// the remapped one.
+pub struct Remap {
+    rules: Vec<Rule>,
+    // What each held input was pressed as, so the release goes to the same place even if the
+    // modifiers changed in between.
+    held: HashMap<Input, Input>,
+}
+
+impl Default for Remap {
+    fn default() -> Self {
+        Self::new(&config().table("remap"))
+    }
+}
+
+impl Remap {
+    pub fn new(entries: &[(String, String)]) -> Self {
+        let rules = entries
+            .iter()
+            .filter_map(|(from, to)| {
+                let rule = Rule::parse(from, to);
+                if rule.is_none() {
+                    println!("ignoring remap {} = {}", from, to);
+                }
+                rule
+            })
+            .collect();
+        Self {
+            rules,
+            held: HashMap::new(),
+        }
+    }
+
+    /// What `input` turns into when pressed with `mods` held.
+    pub fn press(&mut self, input: Input, mods: SDLMod) -> Input {
+        // The rule that asks for the most modifiers wins, and the last one in the file on a tie.
+        let to = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(input, mods))
+            .max_by_key(|rule| rule.modifiers.len())
+            .map_or(input, |rule| rule.to);
+        self.held.insert(input, to);
+        to
+    }
+
+    pub fn release(&mut self, input: Input) -> Input {
+        self.held.remove(&input).unwrap_or(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_defs::{
+        SDLKey_SDLK_ESCAPE, SDLKey_SDLK_PLUS, SDLKey_SDLK_SPACE, SDLKey_SDLK_q, SDLMod_KMOD_NONE,
+    };
+
+    fn remap(entries: &[(&str, &str)]) -> Remap {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect();
+        Remap::new(&entries)
+    }
+
+    #[test]
+    fn keys_and_buttons() {
+        let mut remap = remap(&[("mouse3", "space"), ("space", "mouse1"), ("nonsense", "q")]);
+        let space = Input::Key(SDLKey_SDLK_SPACE);
+        assert_eq!(remap.press(Input::Button(3), SDLMod_KMOD_NONE), space);
+        assert_eq!(remap.press(space, SDLMod_KMOD_NONE), Input::Button(1));
+        assert_eq!(remap.release(Input::Button(3)), space);
+        assert_eq!(
+            remap.press(Input::Button(2), SDLMod_KMOD_NONE),
+            Input::Button(2)
+        );
+    }
+
+    #[test]
+    fn modifiers() {
+        let mut remap = remap(&[("q", "space"), ("ctrl+q", "escape"), ("ctrl++", "mouse1")]);
+        let q = Input::Key(SDLKey_SDLK_q);
+        assert_eq!(
+            remap.press(q, SDLMod_KMOD_NONE),
+            Input::Key(SDLKey_SDLK_SPACE)
+        );
+        assert_eq!(
+            remap.press(q, SDLMod_KMOD_RCTRL),
+            Input::Key(SDLKey_SDLK_ESCAPE)
+        );
+        // Letting go of ctrl first still releases what was pressed.
+        assert_eq!(remap.release(q), Input::Key(SDLKey_SDLK_ESCAPE));
+        assert_eq!(
+            remap.press(Input::Key(SDLKey_SDLK_PLUS), SDLMod_KMOD_LCTRL),
+            Input::Button(1)
+        );
+    }
+}
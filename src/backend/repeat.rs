@@ -0,0 +1,216 @@
+use std::time::{Duration, Instant};
+
+use crate::type_defs::{SDL_keysym, SDLKey_SDLK_COMPOSE, SDLKey_SDLK_NUMLOCK};
+
+/// SDL 1.2's key repeat: off until the game calls SDL_EnableKeyRepeat, and then the last key
+/// pressed sends another SDL_KEYDOWN every `interval` once it's been held for `delay`.
+#[derive(Default)]
+pub struct KeyRepeat {
+    delay: Duration,
+    interval: Duration,
+    held: Option<HeldKey>,
+    // Set when the compositor sends repeated key events itself (wl_keyboard v10), so ours would
+    // be doubled up.
+    compositor_repeats: bool,
+}
+
+struct HeldKey {
+    keycode: u32,
+    keysym: SDL_keysym,
+    pressed_at: Instant,
+    last_sent: Option<Instant>,
+}
+
+impl KeyRepeat {
+    /// SDL_EnableKeyRepeat. A delay of 0 turns repeat off again.
+    pub fn enable(&mut self, delay: i32, interval: i32) -> i32 {
+        if delay < 0 || interval < 0 {
+            println!("SDL_EnableKeyRepeat: delay and interval can't be negative");
+            return -1;
+        }
+        self.delay = Duration::from_millis(delay as u64);
+        self.interval = Duration::from_millis(interval as u64);
+        if delay == 0 {
+            self.held = None;
+        }
+        0
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.delay.is_zero()
+    }
+
+    pub fn set_compositor_repeats(&mut self, compositor_repeats: bool) {
+        self.compositor_repeats = compositor_repeats;
+    }
+
+    /// A new key went down, which takes over from whatever was repeating before.
+    pub fn press(&mut self, keycode: u32, keysym: SDL_keysym) {
+        self.press_at(keycode, keysym, Instant::now());
+    }
+
+    fn press_at(&mut self, keycode: u32, keysym: SDL_keysym, now: Instant) {
+        // SDL doesn't repeat modifiers and lock keys.
+        if !self.enabled() || (SDLKey_SDLK_NUMLOCK..=SDLKey_SDLK_COMPOSE).contains(&keysym.sym) {
+            return;
+        }
+        self.held = Some(HeldKey {
+            keycode,
+            keysym,
+            pressed_at: now,
+            last_sent: None,
+        });
+    }
+
+    pub fn release(&mut self, keycode: u32) {
+        if self
+            .held
+            .as_ref()
+            .is_some_and(|held| held.keycode == keycode)
+        {
+            self.held = None;
+        }
+    }
+
+    /// Forgets the held key, e.g. when the window loses focus.
+    pub fn stop(&mut self) {
+        self.held = None;
+    }
+
+    /// A repeat the compositor sent for us, which only goes through if the game wants repeats
+    /// and it's for the key SDL would be repeating.
+    pub fn compositor_repeat(&self, keycode: u32) -> Option<SDL_keysym> {
+        let held = self.held.as_ref()?;
+        (self.enabled() && held.keycode == keycode).then_some(held.keysym)
+    }
+
+    /// The keysym to send another SDL_KEYDOWN for, if it's time. Called whenever events are
+    /// pumped, like SDL_CheckKeyRepeat.
+    pub fn check(&mut self) -> Option<SDL_keysym> {
+        self.check_at(Instant::now())
+    }
+
+    fn check_at(&mut self, now: Instant) -> Option<SDL_keysym> {
+        if self.compositor_repeats || !self.enabled() {
+            return None;
+        }
+        let held = self.held.as_mut()?;
+        if now.duration_since(held.pressed_at) < self.delay {
+            return None;
+        }
+        match held.last_sent {
+            Some(last) if now.duration_since(last) < self.interval => None,
+            _ => {
+                held.last_sent = Some(now);
+                Some(held.keysym)
+            }
+        }
+    }
+
+    /// How long until check() has another repeat, so SDL_WaitEvent can sleep until then.
+    pub fn next(&self) -> Option<Duration> {
+        self.next_at(Instant::now())
+    }
+
+    fn next_at(&self, now: Instant) -> Option<Duration> {
+        if self.compositor_repeats || !self.enabled() {
+            return None;
+        }
+        let held = self.held.as_ref()?;
+        let due = match held.last_sent {
+            Some(last) => last + self.interval,
+            None => held.pressed_at + self.delay,
+        };
+        Some(due.saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_defs::{SDLKey_SDLK_LSHIFT, SDLKey_SDLK_a, SDLKey_SDLK_b, SDLMod_KMOD_NONE};
+
+    fn keysym(sym: u32) -> SDL_keysym {
+        SDL_keysym {
+            scancode: 0,
+            sym,
+            mod_: SDLMod_KMOD_NONE,
+            unicode: 0,
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn repeated(repeat: &mut KeyRepeat, at: Instant) -> Option<u32> {
+        repeat.check_at(at).map(|keysym| keysym.sym)
+    }
+
+    #[test]
+    fn delay_and_interval() {
+        let start = Instant::now();
+        let mut repeat = KeyRepeat::default();
+        repeat.press_at(38, keysym(SDLKey_SDLK_a), start);
+        // Off until the game asks for it.
+        assert_eq!(repeated(&mut repeat, start + ms(1000)), None);
+
+        repeat.enable(500, 30);
+        repeat.press_at(38, keysym(SDLKey_SDLK_a), start);
+        assert_eq!(repeat.next_at(start), Some(ms(500)));
+        assert_eq!(repeated(&mut repeat, start + ms(499)), None);
+        assert_eq!(repeated(&mut repeat, start + ms(500)), Some(SDLKey_SDLK_a));
+        assert_eq!(repeat.next_at(start + ms(510)), Some(ms(20)));
+        assert_eq!(repeated(&mut repeat, start + ms(520)), None);
+        assert_eq!(repeated(&mut repeat, start + ms(530)), Some(SDLKey_SDLK_a));
+    }
+
+    #[test]
+    fn release_and_takeover() {
+        let start = Instant::now();
+        let mut repeat = KeyRepeat::default();
+        repeat.enable(100, 10);
+        repeat.press_at(38, keysym(SDLKey_SDLK_a), start);
+        repeat.press_at(56, keysym(SDLKey_SDLK_b), start + ms(50));
+        // Letting go of the old key doesn't stop the new one.
+        repeat.release(38);
+        assert_eq!(repeated(&mut repeat, start + ms(100)), None);
+        assert_eq!(repeated(&mut repeat, start + ms(150)), Some(SDLKey_SDLK_b));
+
+        repeat.release(56);
+        assert_eq!(repeated(&mut repeat, start + ms(500)), None);
+        assert_eq!(repeat.next_at(start + ms(500)), None);
+
+        // Modifiers never repeat, and don't take over either.
+        repeat.press_at(38, keysym(SDLKey_SDLK_a), start);
+        repeat.press_at(50, keysym(SDLKey_SDLK_LSHIFT), start);
+        assert_eq!(repeated(&mut repeat, start + ms(100)), Some(SDLKey_SDLK_a));
+    }
+
+    #[test]
+    fn disable() {
+        let start = Instant::now();
+        let mut repeat = KeyRepeat::default();
+        repeat.enable(100, 10);
+        repeat.press_at(38, keysym(SDLKey_SDLK_a), start);
+        assert_eq!(repeat.enable(0, 0), 0);
+        assert!(!repeat.enabled());
+        assert_eq!(repeated(&mut repeat, start + ms(200)), None);
+        assert_eq!(repeat.enable(-1, 0), -1);
+    }
+
+    #[test]
+    fn compositor() {
+        let start = Instant::now();
+        let mut repeat = KeyRepeat::default();
+        repeat.enable(100, 10);
+        repeat.set_compositor_repeats(true);
+        repeat.press_at(38, keysym(SDLKey_SDLK_a), start);
+        assert_eq!(repeated(&mut repeat, start + ms(200)), None);
+        assert_eq!(
+            repeat.compositor_repeat(38).map(|k| k.sym),
+            Some(SDLKey_SDLK_a)
+        );
+        assert!(repeat.compositor_repeat(56).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    config::config,
+    type_defs::{SDL_BUTTON_LEFT, SDL_BUTTON_RIGHT},
+};
+
+/// What a touch comes out as.
+#[derive(PartialEq, Debug)]
+pub enum TouchAction {
+    Motion(i32, i32),
+    Button(u8, bool),
+}
+
+/// SDL 1.2 has no touch, so we pretend to be a mouse. The first finger down moves the pointer
+/// and holds the left button, and with `touch_right_click` a second finger tapped while it's
+/// down is the right button. Any other fingers are ignored.
+pub struct TouchMouse {
+    primary: Option<i32>,
+    secondary: Option<i32>,
+    right_click: bool,
+}
+
+impl Default for TouchMouse {
+    fn default() -> Self {
+        Self::new(config().get_bool("touch_right_click"))
+    }
+}
+
+impl TouchMouse {
+    pub fn new(right_click: bool) -> Self {
+        Self {
+            primary: None,
+            secondary: None,
+            right_click,
+        }
+    }
+
+    pub fn down(&mut self, id: i32, x: i32, y: i32) -> Vec<TouchAction> {
+        if self.primary.is_none() {
+            self.primary = Some(id);
+            vec![
+                TouchAction::Motion(x, y),
+                TouchAction::Button(SDL_BUTTON_LEFT, true),
+            ]
+        } else if self.right_click && self.secondary.is_none() {
+            self.secondary = Some(id);
+            vec![TouchAction::Button(SDL_BUTTON_RIGHT, true)]
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn motion(&mut self, id: i32, x: i32, y: i32) -> Option<TouchAction> {
+        (self.primary == Some(id)).then_some(TouchAction::Motion(x, y))
+    }
+
+    pub fn up(&mut self, id: i32) -> Vec<TouchAction> {
+        if self.primary == Some(id) {
+            self.cancel()
+        } else if self.secondary == Some(id) {
+            self.secondary = None;
+            vec![TouchAction::Button(SDL_BUTTON_RIGHT, false)]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Lets go of everything, e.g. when the compositor takes the touch over for a gesture.
+    pub fn cancel(&mut self) -> Vec<TouchAction> {
+        let mut actions = vec![];
+        if self.secondary.take().is_some() {
+            actions.push(TouchAction::Button(SDL_BUTTON_RIGHT, false));
+        }
+        if self.primary.take().is_some() {
+            actions.push(TouchAction::Button(SDL_BUTTON_LEFT, false));
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primary() {
+        let mut touch = TouchMouse::new(false);
+        assert_eq!(
+            touch.down(3, 10, 20),
+            [
+                TouchAction::Motion(10, 20),
+                TouchAction::Button(SDL_BUTTON_LEFT, true)
+            ]
+        );
+        assert_eq!(touch.down(4, 50, 50), []);
+        assert_eq!(touch.motion(4, 60, 60), None);
+        assert_eq!(touch.motion(3, 15, 20), Some(TouchAction::Motion(15, 20)));
+        assert_eq!(touch.up(3), [TouchAction::Button(SDL_BUTTON_LEFT, false)]);
+        assert_eq!(touch.up(4), []);
+    }
+
+    #[test]
+    fn right_click() {
+        let mut touch = TouchMouse::new(true);
+        touch.down(0, 10, 20);
+        assert_eq!(
+            touch.down(1, 50, 50),
+            [TouchAction::Button(SDL_BUTTON_RIGHT, true)]
+        );
+        assert_eq!(touch.up(1), [TouchAction::Button(SDL_BUTTON_RIGHT, false)]);
+
+        touch.down(1, 50, 50);
+        assert_eq!(
+            touch.cancel(),
+            [
+                TouchAction::Button(SDL_BUTTON_RIGHT, false),
+                TouchAction::Button(SDL_BUTTON_LEFT, false)
+            ]
+        );
+    }
+}
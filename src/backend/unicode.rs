@@ -0,0 +1,286 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::PathBuf,
+    sync::LazyLock,
+};
+
+use xkbcommon_rs::{
+    State,
+    keysym::{KeysymFlags, keysym_from_name},
+};
+
+// xkbcommon-rs can't do compose, so we read the same Compose files libX11 and libxkbcommon do.
+// That covers dead keys as well as the Multi_key ones.
+static COMPOSE: LazyLock<ComposeTable> = LazyLock::new(ComposeTable::load);
+
+/// SDL_EnableUNICODE, and the dead key or compose sequence typed so far.
+#[derive(Default)]
+pub struct Unicode {
+    enabled: bool,
+    sequence: Vec<u32>,
+}
+
+impl Unicode {
+    /// 1 turns it on, 0 off and -1 only asks. Returns what it was before.
+    pub fn enable(&mut self, enable: i32) -> i32 {
+        let previous = self.enabled as i32;
+        if enable >= 0 {
+            self.enabled = enable != 0;
+            self.sequence.clear();
+        }
+        previous
+    }
+
+    /// What goes into `SDL_keysym.unicode` for a key press, given the xkb state from before the
+    /// key went down. 0 for keys that don't type anything, and for the ones that only start or
+    /// continue a compose sequence.
+    pub fn translate(&mut self, xkb: &State, keycode: u32) -> u16 {
+        self.translate_with(&COMPOSE, xkb, keycode)
+    }
+
+    fn translate_with(&mut self, compose: &ComposeTable, xkb: &State, keycode: u32) -> u16 {
+        if !self.enabled {
+            return 0;
+        }
+        let Some(sym) = xkb.key_get_one_sym(keycode) else {
+            return 0;
+        };
+        // Shift and friends don't interrupt a sequence.
+        if sym.is_modifier_key() {
+            return 0;
+        }
+
+        if self.sequence.is_empty() && !compose.prefixes.contains(&vec![sym.raw()]) {
+            return xkb.key_get_utf32(keycode).map_or(0, utf16);
+        }
+        self.sequence.push(sym.raw());
+        if let Some(&c) = compose.sequences.get(&self.sequence) {
+            self.sequence.clear();
+            return utf16(c as u32);
+        }
+        if !compose.prefixes.contains(&self.sequence) {
+            // Like xkbcommon, a sequence that goes nowhere eats the key that broke it.
+            self.sequence.clear();
+        }
+        0
+    }
+}
+
+// SDL 1.2 is UCS-2, there's no way to send anything past the BMP.
+fn utf16(c: u32) -> u16 {
+    u16::try_from(c).unwrap_or(0)
+}
+
+#[derive(Default)]
+struct ComposeTable {
+    sequences: HashMap<Vec<u32>, char>,
+    // Every proper prefix of a sequence, so we know when to keep waiting.
+    prefixes: HashSet<Vec<u32>>,
+}
+
+impl ComposeTable {
+    fn load() -> Self {
+        let mut table = Self::default();
+        let user = env::var_os("XCOMPOSEFILE").map(PathBuf::from).or_else(|| {
+            env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".XCompose"))
+                .filter(|path| path.exists())
+        });
+        match user {
+            Some(path) => table.read(&path, 0),
+            None => {
+                if let Some(path) = system_compose_file() {
+                    table.read(&path, 0);
+                }
+            }
+        }
+        table
+    }
+
+    fn read(&mut self, path: &PathBuf, depth: u32) {
+        // Compose files can include each other, but not forever.
+        if depth > 4 {
+            return;
+        }
+        let Ok(contents) = fs::read_to_string(path) else {
+            println!("couldn't read compose file {}", path.display());
+            return;
+        };
+        self.parse(&contents, depth);
+    }
+
+    fn parse(&mut self, contents: &str, depth: u32) {
+        for line in contents.lines() {
+            let line = line.trim();
+            if let Some(include) = line.strip_prefix("include") {
+                let include = include.trim().trim_matches('"');
+                if include.contains("%L") {
+                    if let Some(system) = system_compose_file() {
+                        self.read(&system, depth + 1);
+                    }
+                } else {
+                    let home = env::var("HOME").unwrap_or_default();
+                    self.read(&PathBuf::from(include.replace("%H", &home)), depth + 1);
+                }
+            } else if line.starts_with('<') {
+                self.parse_line(line);
+            }
+        }
+    }
+
+    // `<dead_acute> <a> : "á" aacute # LATIN SMALL LETTER A WITH ACUTE`
+    fn parse_line(&mut self, line: &str) {
+        let Some((keys, result)) = line.split_once(':') else {
+            return;
+        };
+        let sequence: Option<Vec<u32>> = keys
+            .split_whitespace()
+            .map(|key| {
+                let name = key.strip_prefix('<')?.strip_suffix('>')?;
+                keysym_from_name(name, KeysymFlags::NO_FLAGS).map(|sym| sym.raw())
+            })
+            .collect();
+        let Some(sequence) = sequence.filter(|s| !s.is_empty()) else {
+            return;
+        };
+
+        let result = result.trim();
+        let c = match result.strip_prefix('"') {
+            // Results longer than a character are no use to us anyways.
+            Some(string) => string.strip_prefix('\\').unwrap_or(string).chars().next(),
+            None => result
+                .split_whitespace()
+                .next()
+                .and_then(|name| keysym_from_name(name, KeysymFlags::NO_FLAGS))
+                .and_then(|sym| sym.key_char()),
+        };
+        let Some(c) = c else {
+            return;
+        };
+
+        for len in 1..sequence.len() {
+            self.prefixes.insert(sequence[..len].to_vec());
+        }
+        self.sequences.insert(sequence, c);
+    }
+}
+
+/// The system Compose file for the user's locale, going by libX11's compose.dir.
+fn system_compose_file() -> Option<PathBuf> {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|locale| !locale.is_empty())
+        .unwrap_or_default();
+    let base = PathBuf::from("/usr/share/X11/locale");
+    let dir = fs::read_to_string(base.join("compose.dir")).unwrap_or_default();
+
+    let file = [locale.as_str(), "en_US.UTF-8"].iter().find_map(|locale| {
+        dir.lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .find(|(_, name)| name.trim() == *locale)
+            .map(|(file, _)| file.trim_end_matches(':').to_string())
+    })?;
+    Some(base.join(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use xkbcommon_rs::{
+        Context, Keymap,
+        xkb_context::ContextFlags,
+        xkb_keymap::{CompileFlags, RuleNames},
+    };
+
+    use super::*;
+
+    // evdev codes plus 8.
+    const KEY_E: u32 = 18 + 8;
+    const KEY_K: u32 = 37 + 8;
+    const KEY_APOSTROPHE: u32 = 40 + 8;
+
+    const COMPOSE_FILE: &str = r#"
+# Dead keys
+<dead_acute> <e>        : "é"   eacute # LATIN SMALL LETTER E WITH ACUTE
+<dead_acute> <a>        : "á"   aacute
+<dead_grave> <a>        : agrave
+<Multi_key> <o> <c>     : "©"   copyright
+<Multi_key> <backslash> <o> : "\\"
+<dead_acute> <nonsense> : "x"
+"#;
+
+    fn table() -> ComposeTable {
+        let mut table = ComposeTable::default();
+        table.parse(COMPOSE_FILE, 0);
+        table
+    }
+
+    fn sym(name: &str) -> u32 {
+        keysym_from_name(name, KeysymFlags::NO_FLAGS).unwrap().raw()
+    }
+
+    // US international, where the apostrophe is a dead acute.
+    fn intl() -> State {
+        let keymap = Keymap::new_from_names(
+            Context::new(ContextFlags::NO_FLAGS).unwrap(),
+            Some(RuleNames {
+                rules: None,
+                model: None,
+                layout: Some("us".into()),
+                variant: Some("intl".into()),
+                options: None,
+            }),
+            CompileFlags::NO_FLAGS,
+        )
+        .unwrap();
+        State::new(keymap)
+    }
+
+    #[test]
+    fn parse() {
+        let table = table();
+        for (sequence, c) in [
+            (vec![sym("dead_acute"), sym("e")], 'é'),
+            (vec![sym("dead_acute"), sym("a")], 'á'),
+            (vec![sym("dead_grave"), sym("a")], 'à'),
+            (vec![sym("Multi_key"), sym("o"), sym("c")], '©'),
+            (vec![sym("Multi_key"), sym("backslash"), sym("o")], '\\'),
+        ] {
+            assert_eq!(table.sequences.get(&sequence), Some(&c), "{}", c);
+        }
+        assert_eq!(table.sequences.len(), 5);
+        assert!(table.prefixes.contains(&vec![sym("Multi_key"), sym("o")]));
+        assert!(!table.prefixes.contains(&vec![sym("dead_acute"), sym("e")]));
+    }
+
+    #[test]
+    fn dead_keys() {
+        let table = table();
+        let xkb = intl();
+        let mut unicode = Unicode::default();
+        unicode.enable(1);
+
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_APOSTROPHE), 0);
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_E), 'é' as u16);
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_E), 'e' as u16);
+
+        // There's no acute k, so that's the end of it, k included.
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_APOSTROPHE), 0);
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_K), 0);
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_E), 'e' as u16);
+    }
+
+    #[test]
+    fn disabled() {
+        let table = table();
+        let xkb = intl();
+        let mut unicode = Unicode::default();
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_E), 0);
+        assert_eq!(unicode.enable(-1), 0);
+        assert_eq!(unicode.enable(1), 0);
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_E), 'e' as u16);
+        assert_eq!(unicode.enable(0), 1);
+        assert_eq!(unicode.translate_with(&table, &xkb, KEY_E), 0);
+    }
+}
@@ -0,0 +1,167 @@
+use std::ptr::null_mut;
+
+use crate::type_defs::{SDL_PixelFormat, SDL_Rect, SDL_VideoInfo};
+
+// Wayland compositors only ever advertise the mode the monitor is currently in, and a window can
+// be any size that fits on it anyways. So on top of whatever the display reports we offer the
+// usual sizes an SDL 1.2 game would expect to see from a real video driver.
+const COMMON_MODES: &[(i32, i32)] = &[
+    (640, 480),
+    (800, 600),
+    (1024, 768),
+    (1152, 864),
+    (1280, 720),
+    (1280, 800),
+    (1280, 960),
+    (1280, 1024),
+    (1366, 768),
+    (1440, 900),
+    (1600, 900),
+    (1600, 1200),
+    (1680, 1050),
+    (1920, 1080),
+    (1920, 1200),
+    (2560, 1440),
+    (2560, 1600),
+    (3840, 2160),
+];
+
+/// Everything SDL_GetVideoInfo and SDL_ListModes hand out pointers to, so it has to stay put for as
+/// long as the window does.
+pub struct VideoModes {
+    info: SDL_VideoInfo,
+    format: SDL_PixelFormat,
+    rects: Vec<SDL_Rect>,
+    rect_ptrs: Vec<*mut SDL_Rect>,
+}
+
+impl VideoModes {
+    pub fn new() -> Self {
+        Self {
+            info: SDL_VideoInfo {
+                _bitfield_align_1: [],
+                _bitfield_1: SDL_VideoInfo::new_bitfield_1(1, 1, 0, 0, 0, 0, 0, 0, 0),
+                // SDL's X11 driver doesn't know this either and reports 0.
+                video_mem: 0,
+                vfmt: null_mut(),
+                current_w: 0,
+                current_h: 0,
+            },
+            // What we actually render into: XRGB8888.
+            format: SDL_PixelFormat {
+                palette: null_mut(),
+                BitsPerPixel: 32,
+                BytesPerPixel: 4,
+                Rloss: 0,
+                Gloss: 0,
+                Bloss: 0,
+                Aloss: 8,
+                Rshift: 16,
+                Gshift: 8,
+                Bshift: 0,
+                Ashift: 0,
+                Rmask: 0x00FF0000,
+                Gmask: 0x0000FF00,
+                Bmask: 0x000000FF,
+                Amask: 0,
+                colorkey: 0,
+                alpha: 255,
+            },
+            rects: vec![],
+            rect_ptrs: vec![],
+        }
+    }
+
+    pub fn video_info(&mut self, current: Option<(i32, i32)>) -> *mut SDL_VideoInfo {
+        let (w, h) = current.unwrap_or((0, 0));
+        self.info.current_w = w;
+        self.info.current_h = h;
+        self.info.vfmt = &mut self.format;
+        &mut self.info
+    }
+
+    /// Builds the NULL-terminated, largest-first array SDL_ListModes returns. Before we know the
+    /// display there's nothing to list, and NULL would mean no mode works at all, so it's -1 for
+    /// "any size" instead.
+    pub fn list_modes(&mut self, modes: &[(i32, i32)]) -> *mut *mut SDL_Rect {
+        if modes.is_empty() {
+            return usize::MAX as *mut *mut SDL_Rect;
+        }
+        self.rects = modes
+            .iter()
+            .map(|&(w, h)| SDL_Rect {
+                x: 0,
+                y: 0,
+                w: w as u16,
+                h: h as u16,
+            })
+            .collect();
+        self.rect_ptrs = self.rects.iter_mut().map(|r| r as *mut SDL_Rect).collect();
+        self.rect_ptrs.push(null_mut());
+        self.rect_ptrs.as_mut_ptr()
+    }
+}
+
+/// The modes a display of `size` can show a window at, largest first. `native` are the modes the
+/// display itself advertised, in the same units as `size`.
+pub fn fitting_modes(size: (i32, i32), native: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let mut modes: Vec<(i32, i32)> = COMMON_MODES
+        .iter()
+        .chain(native.iter())
+        .chain(std::iter::once(&size))
+        .copied()
+        .filter(|&(w, h)| w > 0 && h > 0 && w <= size.0 && h <= size.1)
+        .collect();
+    sort_modes(&mut modes);
+    modes
+}
+
+/// SDL orders modes from largest to smallest, without duplicates.
+pub fn sort_modes(modes: &mut Vec<(i32, i32)>) {
+    modes.sort_by_key(|&(w, h)| std::cmp::Reverse((w * h, w)));
+    modes.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fitting() {
+        let modes = fitting_modes((1366, 768), &[(1366, 768), (1360, 768), (1024, 768)]);
+        assert_eq!(
+            modes,
+            [
+                (1366, 768),
+                (1360, 768),
+                (1280, 720),
+                (1024, 768),
+                (800, 600),
+                (640, 480)
+            ]
+        );
+        // A display too small for any of the usual ones still gets its own size.
+        assert_eq!(fitting_modes((320, 200), &[]), [(320, 200)]);
+    }
+
+    #[test]
+    fn sorting() {
+        let mut modes = vec![(640, 480), (1280, 720), (640, 480), (720, 1280), (0, 0)];
+        sort_modes(&mut modes);
+        assert_eq!(modes, [(1280, 720), (720, 1280), (640, 480), (0, 0)]);
+    }
+
+    #[test]
+    fn listing() {
+        let mut video_modes = VideoModes::new();
+        assert_eq!(video_modes.list_modes(&[]) as usize, usize::MAX);
+
+        let list = video_modes.list_modes(&[(800, 600), (640, 480)]);
+        let rects: Vec<(u16, u16)> = (0..)
+            .map(|i| unsafe { *list.add(i) })
+            .take_while(|rect| !rect.is_null())
+            .map(|rect| unsafe { ((*rect).w, (*rect).h) })
+            .collect();
+        assert_eq!(rects, [(800, 600), (640, 480)]);
+    }
+}
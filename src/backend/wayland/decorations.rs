@@ -0,0 +1,342 @@
+use std::{io::Write, os::fd::AsFd};
+
+use wayland_client::{
+    Dispatch, QueueHandle, delegate_noop,
+    protocol::{
+        wl_buffer::WlBuffer, wl_shm, wl_subcompositor::WlSubcompositor,
+        wl_subsurface::WlSubsurface, wl_surface::WlSurface,
+    },
+};
+use wayland_protocols::{
+    wp::cursor_shape::v1::client::{
+        wp_cursor_shape_device_v1::{Shape, WpCursorShapeDeviceV1},
+        wp_cursor_shape_manager_v1::WpCursorShapeManagerV1,
+    },
+    xdg::decoration::zv1::client::zxdg_toplevel_decoration_v1::{self, ZxdgToplevelDecorationV1},
+};
+
+use crate::backend::wayland::WaylandState;
+
+// GNOME won't do server-side decorations, so on compositors like that we draw a bare-bones title
+// bar ourselves. It's a subsurface sitting above the game's surface, so the game never has to
+// know it's there.
+
+pub const TITLE_HEIGHT: i32 = 24;
+const BUTTON_WIDTH: i32 = 24;
+// The font is 5x7, drawn at twice the size so it's actually readable.
+const FONT_SCALE: i32 = 2;
+const GLYPH_ADVANCE: i32 = 6 * FONT_SCALE;
+
+const BACKGROUND: u32 = 0xFF303030;
+const FOREGROUND: u32 = 0xFFE0E0E0;
+
+const BTN_LEFT: u32 = 0x110;
+
+#[derive(PartialEq)]
+enum Button {
+    Close,
+    Maximize,
+}
+
+pub struct Decorations {
+    surface: WlSurface,
+    subsurface: WlSubsurface,
+    buffer: Option<WlBuffer>,
+    pointer_x: f64,
+}
+
+impl Decorations {
+    pub fn is_surface(&self, surface: &WlSurface) -> bool {
+        self.surface == *surface
+    }
+
+    fn button_at(&self, width: i32, can_maximize: bool) -> Option<Button> {
+        let x = self.pointer_x as i32;
+        if x >= width - BUTTON_WIDTH {
+            Some(Button::Close)
+        } else if can_maximize && x >= width - BUTTON_WIDTH * 2 {
+            Some(Button::Maximize)
+        } else {
+            None
+        }
+    }
+
+    fn destroy(self) {
+        self.subsurface.destroy();
+        self.surface.destroy();
+        if let Some(buffer) = self.buffer {
+            buffer.destroy();
+        }
+    }
+}
+
+impl WaylandState {
+    /// Switches to our own title bar, if it isn't already on.
+    pub fn enable_csd(&mut self, qh: &QueueHandle<Self>) {
+        if self.decorations.is_some() || self.xdg_surface.is_none() {
+            return;
+        }
+        let (Some(compositor), Some(subcompositor), Some(parent)) = (
+            self.compositor.as_ref(),
+            self.subcompositor.as_ref(),
+            self.compositor_surface.as_ref(),
+        ) else {
+            println!("can't draw our own decorations without wl_subcompositor");
+            return;
+        };
+
+        let surface = compositor.create_surface(qh, ());
+        let subsurface = subcompositor.get_subsurface(&surface, parent, qh, ());
+        subsurface.set_position(0, -TITLE_HEIGHT);
+
+        self.decorations = Some(Decorations {
+            surface,
+            subsurface,
+            buffer: None,
+            pointer_x: 0.0,
+        });
+        self.draw_decorations(qh);
+        self.update_window_geometry();
+    }
+
+    pub fn disable_csd(&mut self) {
+        if let Some(decorations) = self.decorations.take() {
+            decorations.destroy();
+            self.update_window_geometry();
+        }
+    }
+
+    /// Tells the compositor how big the window is, title bar included, and keeps it from being
+    /// resized since the game only ever renders at the size it asked for. Maximized is the one
+    /// exception, where it gets scaled up instead.
+    pub fn update_window_geometry(&self) {
+        let (Some(xdg_surface), Some(toplevel)) =
+            (self.xdg_surface.as_ref(), self.xdg_top_level.as_ref())
+        else {
+            return;
+        };
+        let (width, height) = self.shown_size();
+        if self.decorations.is_some() {
+            xdg_surface.set_window_geometry(0, -TITLE_HEIGHT, width, height + TITLE_HEIGHT);
+        } else {
+            xdg_surface.set_window_geometry(0, 0, width, height);
+        }
+        if self.maximized.is_some() {
+            toplevel.set_min_size(0, 0);
+            toplevel.set_max_size(0, 0);
+        } else {
+            let title = self.title_height();
+            toplevel.set_min_size(width, height + title);
+            toplevel.set_max_size(width, height + title);
+        }
+    }
+
+    pub fn title_height(&self) -> i32 {
+        if self.decorations.is_some() {
+            TITLE_HEIGHT
+        } else {
+            0
+        }
+    }
+
+    pub fn draw_decorations(&mut self, qh: &QueueHandle<Self>) {
+        let Some(wl_shm) = self.wl_shm.as_ref() else {
+            return;
+        };
+        // Without wp_viewporter there's no scaling the game up, so no maximizing either.
+        let buttons = if self.viewport.is_some() { 2 } else { 1 };
+        let width = self.shown_size().0.max(BUTTON_WIDTH * buttons);
+        let Some(decorations) = self.decorations.as_mut() else {
+            return;
+        };
+
+        let mut pixels = vec![BACKGROUND; (width * TITLE_HEIGHT) as usize];
+        let max_chars = ((width - BUTTON_WIDTH * buttons - 8) / GLYPH_ADVANCE).max(0) as usize;
+        let y = (TITLE_HEIGHT - 7 * FONT_SCALE) / 2;
+        for (i, c) in self.title.chars().take(max_chars).enumerate() {
+            draw_glyph(&mut pixels, width, 8 + i as i32 * GLYPH_ADVANCE, y, c);
+        }
+        draw_close_button(&mut pixels, width, width - BUTTON_WIDTH);
+        if buttons == 2 {
+            draw_maximize_button(&mut pixels, width, width - BUTTON_WIDTH * 2);
+        }
+
+        let bytes: Vec<u8> = pixels.iter().flat_map(|p| p.to_le_bytes()).collect();
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&bytes).unwrap();
+        let pool = wl_shm.create_pool(file.as_fd(), bytes.len() as i32, qh, ());
+        let buffer = pool.create_buffer(
+            0,
+            width,
+            TITLE_HEIGHT,
+            width * 4,
+            wl_shm::Format::Argb8888,
+            qh,
+            (),
+        );
+        pool.destroy();
+
+        decorations.surface.attach(Some(&buffer), 0, 0);
+        decorations.surface.damage(0, 0, width, TITLE_HEIGHT);
+        decorations.surface.commit();
+        if let Some(old) = decorations.buffer.replace(buffer) {
+            old.destroy();
+        }
+    }
+
+    pub fn decoration_motion(&mut self, x: f64) {
+        if let Some(decorations) = self.decorations.as_mut() {
+            decorations.pointer_x = x;
+        }
+    }
+
+    pub fn decoration_button(&mut self, button: u32, serial: u32) {
+        let Some(decorations) = self.decorations.as_ref() else {
+            return;
+        };
+        if button != BTN_LEFT {
+            return;
+        }
+        match decorations.button_at(self.shown_size().0, self.viewport.is_some()) {
+            Some(Button::Close) => {
+                self.running = false;
+            }
+            Some(Button::Maximize) => {
+                let toplevel = self.xdg_top_level();
+                if self.maximized.is_some() {
+                    toplevel.unset_maximized();
+                } else {
+                    // Compositors won't maximize a window that says it can't get any bigger.
+                    toplevel.set_max_size(0, 0);
+                    toplevel.set_maximized();
+                }
+            }
+            None => {
+                if let Some(seat) = self.seat.as_ref() {
+                    self.xdg_top_level()._move(seat, serial);
+                }
+            }
+        }
+    }
+
+    /// The game hides the cursor, but it needs to be visible to hit anything on the title bar.
+    pub fn show_decoration_cursor(&mut self, serial: u32, qh: &QueueHandle<Self>) {
+        if self.cursor_shape_device.is_none()
+            && let (Some(manager), Some(pointer)) =
+                (self.cursor_shape_manager.as_ref(), self.pointer.as_ref())
+        {
+            self.cursor_shape_device = Some(manager.get_pointer(pointer, qh, ()));
+        }
+        if let Some(device) = self.cursor_shape_device.as_ref() {
+            device.set_shape(serial, Shape::Default);
+        }
+    }
+}
+
+fn fill_rect(pixels: &mut [u32], stride: i32, x: i32, y: i32, w: i32, h: i32, color: u32) {
+    for py in y.max(0)..(y + h).min(TITLE_HEIGHT) {
+        for px in x.max(0)..(x + w).min(stride) {
+            pixels[(py * stride + px) as usize] = color;
+        }
+    }
+}
+
+fn draw_glyph(pixels: &mut [u32], stride: i32, x: i32, y: i32, c: char) {
+    let idx = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    for (col, bits) in FONT[idx].iter().enumerate() {
+        for row in 0..7 {
+            if bits & (1 << row) != 0 {
+                fill_rect(
+                    pixels,
+                    stride,
+                    x + col as i32 * FONT_SCALE,
+                    y + row * FONT_SCALE,
+                    FONT_SCALE,
+                    FONT_SCALE,
+                    FOREGROUND,
+                );
+            }
+        }
+    }
+}
+
+fn draw_close_button(pixels: &mut [u32], stride: i32, x: i32) {
+    for i in 0..10 {
+        fill_rect(pixels, stride, x + 7 + i, 7 + i, 2, 2, FOREGROUND);
+        fill_rect(pixels, stride, x + 16 - i, 7 + i, 2, 2, FOREGROUND);
+    }
+}
+
+fn draw_maximize_button(pixels: &mut [u32], stride: i32, x: i32) {
+    fill_rect(pixels, stride, x + 7, 7, 11, 2, FOREGROUND);
+    fill_rect(pixels, stride, x + 7, 16, 11, 2, FOREGROUND);
+    fill_rect(pixels, stride, x + 7, 7, 2, 11, FOREGROUND);
+    fill_rect(pixels, stride, x + 16, 7, 2, 11, FOREGROUND);
+}
+
+impl Dispatch<ZxdgToplevelDecorationV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &ZxdgToplevelDecorationV1,
+        event: <ZxdgToplevelDecorationV1 as wayland_client::Proxy>::Event,
+        data: &(),
+        conn: &wayland_client::Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        // The compositor is allowed to refuse server-side decorations, in which case it's on us.
+        if let zxdg_toplevel_decoration_v1::Event::Configure { mode } = event {
+            match mode {
+                wayland_client::WEnum::Value(zxdg_toplevel_decoration_v1::Mode::ServerSide) => {
+                    state.disable_csd();
+                }
+                _ => {
+                    state.enable_csd(qhandle);
+                }
+            }
+        }
+    }
+}
+delegate_noop!(WaylandState: ignore WlSubcompositor);
+delegate_noop!(WaylandState: ignore WlSubsurface);
+delegate_noop!(WaylandState: ignore WpCursorShapeManagerV1);
+delegate_noop!(WaylandState: ignore WpCursorShapeDeviceV1);
+
+// The classic 5x7 font, one byte per column with the top row in the lowest bit. Covers ' ' to '~'.
+#[rustfmt::skip]
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00], [0x08, 0x2A, 0x1C, 0x2A, 0x08], [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x00, 0x08, 0x14, 0x22, 0x41], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x41, 0x22, 0x14, 0x08, 0x00], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], [0x7F, 0x09, 0x09, 0x01, 0x01],
+    [0x3E, 0x41, 0x41, 0x51, 0x32], [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x7F, 0x20, 0x18, 0x20, 0x7F], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x03, 0x04, 0x78, 0x04, 0x03], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x00, 0x7F, 0x41, 0x41],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x41, 0x41, 0x7F, 0x00, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7E, 0x09, 0x01, 0x02], [0x08, 0x14, 0x54, 0x54, 0x3C],
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x00, 0x7F, 0x10, 0x28, 0x44], [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C], [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],
+];
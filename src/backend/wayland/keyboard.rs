@@ -5,13 +5,15 @@ use std::{
     os::fd::{AsRawFd, FromRawFd},
 };
 
-use crate::{type_defs::SDL_keysym, xcb::sdl_key_from_keysym};
-use wayland_client::{Dispatch, protocol::wl_keyboard::WlKeyboard};
+use wayland_client::{
+    Dispatch, Proxy, WEnum,
+    protocol::wl_keyboard::{KeyState, WlKeyboard},
+};
 use xkbcommon_rs::{
     Context, Keymap, KeymapFormat, State, xkb_context::ContextFlags, xkb_keymap::CompileFlags,
 };
 
-use crate::backend::wayland::WaylandState;
+use crate::backend::{events, remap::Input, wayland::WaylandState};
 
 impl Dispatch<WlKeyboard, ()> for WaylandState {
     fn event(
@@ -36,8 +38,7 @@ impl Dispatch<WlKeyboard, ()> for WaylandState {
                 )
                 .unwrap();
 
-                state.xkb_state = Some(State::new(keymap.clone()));
-                state.xkb_keymap = Some(keymap);
+                state.xkb_state = Some(State::new(keymap));
             }
             wayland_client::protocol::wl_keyboard::Event::Enter {
                 serial,
@@ -58,51 +59,67 @@ impl Dispatch<WlKeyboard, ()> for WaylandState {
                 state: keystate,
             } => {
                 let keycode = key_ + 8;
-                if let Some(keymap) = state.xkb_keymap.as_ref() {
-                    if let Some(key_state) = state.xkb_state.as_ref() {
-                        if let wayland_client::WEnum::Value(keystate) = keystate {
-                            let layout = key_state.key_get_layout(keycode).unwrap();
-                            let level = keymap.num_levels_for_key(keycode, layout) - 1;
-                            let syms_out = keymap
-                                .key_get_syms_by_level(keycode, layout, level)
-                                .unwrap();
-
-                            for sym in syms_out {
-                                let key = sdl_key_from_keysym(sym);
-                                match keystate {
-                                    wayland_client::protocol::wl_keyboard::KeyState::Released => {
-                                        state.active_keysyms.push((
-                                            0,
-                                            SDL_keysym {
-                                                scancode: 25,
-                                                sym: key,
-                                                mod_: 0,
-                                                unicode: 0,
-                                            },
-                                        ));
-                                        state.keys[key as usize] = 0
-                                    }
-                                    wayland_client::protocol::wl_keyboard::KeyState::Pressed
-                                    | wayland_client::protocol::wl_keyboard::KeyState::Repeated => {
-                                        state.active_keysyms.push((
-                                            1,
-                                            SDL_keysym {
-                                                scancode: 25,
-                                                sym: key,
-                                                mod_: 0,
-                                                unicode: 0,
-                                            },
-                                        ));
-
-                                        state.keys[key as usize] = 1 as u32;
-                                    }
-                                    _ => {}
-                                }
-                            }
+                let (Some(xkb_state), WEnum::Value(keystate)) =
+                    (state.xkb_state.as_ref(), keystate)
+                else {
+                    return;
+                };
+                let pressed = match keystate {
+                    KeyState::Pressed => true,
+                    KeyState::Released => false,
+                    KeyState::Repeated => {
+                        if let Some(keysym) = state.key_repeat.compositor_repeat(keycode) {
+                            state.events.push(events::key_event(true, keysym));
                         }
+                        return;
                     }
+                    _ => return,
+                };
+
+                let (input, unicode) = if pressed {
+                    let key = state.pressed_keys.press(xkb_state, keycode);
+                    let unicode = state.unicode.translate(xkb_state, keycode);
+                    let input = state
+                        .remap
+                        .press(Input::Key(key), state.input.modifiers().get());
+                    // Whatever the key typed isn't what it's been remapped to.
+                    (input, if input == Input::Key(key) { unicode } else { 0 })
+                } else {
+                    let key = state.pressed_keys.release(xkb_state, keycode);
+                    (state.remap.release(Input::Key(key)), 0)
+                };
+                state.send_input(input, pressed, keycode, unicode);
+            }
+            wayland_client::protocol::wl_keyboard::Event::Modifiers {
+                serial,
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+            } => {
+                if let Some(xkb_state) = state.xkb_state.as_mut() {
+                    xkb_state.update_mask(
+                        mods_depressed,
+                        mods_latched,
+                        mods_locked,
+                        0,
+                        0,
+                        group as usize,
+                    );
+                    state.input.modifiers().sync(xkb_state);
                 }
             }
+            wayland_client::protocol::wl_keyboard::Event::Leave { serial, surface } => {
+                state.key_repeat.stop();
+            }
+            wayland_client::protocol::wl_keyboard::Event::RepeatInfo { rate, delay } => {
+                // Since v10 a rate of 0 means the compositor sends the repeats itself. Before that
+                // it only means the user turned repeat off for the desktop, and SDL 1.2 always
+                // repeated keys itself whenever the game asked, so our own timer keeps going.
+                state
+                    .key_repeat
+                    .set_compositor_repeats(rate == 0 && proxy.version() >= 10);
+            }
             _ => {}
         }
         // match event {}
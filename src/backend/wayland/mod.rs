@@ -2,30 +2,39 @@
 use std::{
     ffi::{CStr, c_char, c_void},
     io::ErrorKind,
+    os::fd::{AsRawFd, RawFd},
     process::exit,
     ptr::{null, null_mut},
     time::SystemTime,
 };
 
+mod decorations;
 mod fifo;
 mod keyboard;
+mod output;
 mod pointer;
 mod seat;
+mod touch;
 mod xdg;
 
+use image::RgbaImage;
 use wayland_client::{
     Connection, EventQueue, Proxy, QueueHandle,
-    backend::WaylandError,
+    backend::{ReadEventsGuard, WaylandError},
     delegate_noop,
     protocol::{
         wl_buffer::WlBuffer,
         wl_compositor::WlCompositor,
         wl_display::WlDisplay,
+        wl_output::WlOutput,
         wl_pointer::WlPointer,
         wl_region::WlRegion,
+        wl_seat::WlSeat,
         wl_shm::{self, WlShm},
         wl_shm_pool::WlShmPool,
+        wl_subcompositor::WlSubcompositor,
         wl_surface::WlSurface,
+        wl_touch::WlTouch,
     },
 };
 use wayland_client::{
@@ -38,7 +47,18 @@ use wayland_client::{
 };
 use wayland_egl::WlEglSurface;
 use wayland_protocols::{
-    wp::fifo::v1::client::{wp_fifo_manager_v1::WpFifoManagerV1, wp_fifo_v1::WpFifoV1},
+    wp::{
+        content_type::v1::client::{
+            wp_content_type_manager_v1::WpContentTypeManagerV1,
+            wp_content_type_v1::{self, WpContentTypeV1},
+        },
+        cursor_shape::v1::client::{
+            wp_cursor_shape_device_v1::WpCursorShapeDeviceV1,
+            wp_cursor_shape_manager_v1::WpCursorShapeManagerV1,
+        },
+        fifo::v1::client::{wp_fifo_manager_v1::WpFifoManagerV1, wp_fifo_v1::WpFifoV1},
+        viewporter::client::{wp_viewport::WpViewport, wp_viewporter::WpViewporter},
+    },
     xdg::{
         shell::client::xdg_wm_base,
         toplevel_icon::v1::client::{
@@ -51,18 +71,34 @@ use wayland_sys::{
     client::{wayland_client_handle, wl_display},
     ffi_dispatch,
 };
-use xkbcommon_rs::{Keymap, State};
+use xkbcommon_rs::State;
 
 use crate::{
-    backend::Window,
-    egl::{EGL, EGL_TRUE, EGLBoolean, EGLDisplay, EGLSurface},
-    type_defs::{
-        self, SDL_EventType, SDL_Rect, SDL_Surface, SDL_VideoInfo, SDL_keysym, SDLKey,
-        SDLKey_SDLK_LAST,
+    backend::{
+        Window, events,
+        input::{InputState, Wheel},
+        keys::PressedKeys,
+        queue::Queue,
+        remap::{Input, Remap},
+        repeat::KeyRepeat,
+        touch::{TouchAction, TouchMouse},
+        unicode::Unicode,
+        video::{self, VideoModes},
+        wayland::{decorations::Decorations, output::Output},
     },
+    config,
+    egl::{EGL, EGL_TRUE, EGLBoolean, EGLDisplay, EGLSurface},
+    joystick::Joysticks,
+    type_defs::{self, SDL_BUTTON_WHEELDOWN, SDL_BUTTON_WHEELUP, SDL_Rect, SDL_Surface},
 };
 use wayland_protocols::{
-    wp::pointer_warp::v1::client::wp_pointer_warp_v1::WpPointerWarpV1,
+    wp::{
+        pointer_warp::v1::client::wp_pointer_warp_v1::WpPointerWarpV1,
+        relative_pointer::zv1::client::{
+            zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
+            zwp_relative_pointer_v1::ZwpRelativePointerV1,
+        },
+    },
     xdg::{
         decoration::zv1::client::{
             zxdg_decoration_manager_v1::ZxdgDecorationManagerV1,
@@ -71,6 +107,7 @@ use wayland_protocols::{
         shell::client::{
             xdg_surface::XdgSurface, xdg_toplevel::XdgToplevel, xdg_wm_base::XdgWmBase,
         },
+        xdg_output::zv1::client::zxdg_output_manager_v1::ZxdgOutputManagerV1,
     },
 };
 
@@ -82,6 +119,11 @@ pub struct WaylandState {
     wm_base: Option<XdgWmBase>,
     xdg_surface: Option<XdgSurface>,
     xdg_top_level: Option<XdgToplevel>,
+    subcompositor: Option<WlSubcompositor>,
+    viewporter: Option<WpViewporter>,
+    // Scales the game's surface up when the window's maximized.
+    viewport: Option<WpViewport>,
+    seat: Option<WlSeat>,
     native_surface: EGLSurface,
     egl_surface: Option<WlEglSurface>,
     egl: Option<EGL>,
@@ -91,16 +133,35 @@ pub struct WaylandState {
     running: bool,
     quit_attempts: u8,
     pointer: Option<WlPointer>,
+    wl_touch: Option<WlTouch>,
     pointer_serial: u32,
-
-    pointer_events: Vec<wayland_client::protocol::wl_pointer::Event>,
-    last_pointer_x: f64,
-    last_pointer_y: f64,
-
-    keys: Vec<SDLKey>,
-    active_keysyms: Vec<(u32, SDL_keysym)>,
-    xkb_keymap: Option<Keymap>,
+    // Whether the pointer is over our title bar rather than the game.
+    pointer_on_decorations: bool,
+
+    events: Queue,
+    input: InputState,
+    wheel: Wheel,
+    touch: TouchMouse,
+    joysticks: Joysticks,
     xkb_state: Option<State>,
+    key_repeat: KeyRepeat,
+    pressed_keys: PressedKeys,
+    unicode: Unicode,
+    remap: Remap,
+
+    outputs: Vec<Output>,
+    // Outputs the window is currently on, in the order it entered them.
+    surface_outputs: Vec<WlOutput>,
+    xdg_output_manager: Option<ZxdgOutputManagerV1>,
+
+    // The size the game asked for, which is what we keep the window at.
+    window_size: (i32, i32),
+    title: String,
+    // Our own title bar, for when the compositor won't draw one.
+    decorations: Option<Decorations>,
+    // What the compositor maximized us to, title bar not included. The game keeps rendering at
+    // window_size and the viewport stretches it to this.
+    maximized: Option<(i32, i32)>,
 
     // resize_happened: bool,
     // resized_x: i32,
@@ -111,10 +172,19 @@ pub struct WaylandState {
     decoration_manager: Option<ZxdgDecorationManagerV1>,
     toplevel_decoration: Option<ZxdgToplevelDecorationV1>,
     pointer_warp: Option<WpPointerWarpV1>,
+    relative_pointer_manager: Option<ZwpRelativePointerManagerV1>,
+    relative_pointer: Option<ZwpRelativePointerV1>,
     toplevel_icon_manager: Option<XdgToplevelIconManagerV1>,
     toplevel_icon: Option<XdgToplevelIconV1>,
+    // Sizes the compositor would like icons in, and the icon the game set if it did.
+    icon_sizes: Vec<i32>,
+    icon: Option<RgbaImage>,
     fifo_manager: Option<WpFifoManagerV1>,
     fifo: Option<WpFifoV1>,
+    content_type_manager: Option<WpContentTypeManagerV1>,
+    content_type: Option<WpContentTypeV1>,
+    cursor_shape_manager: Option<WpCursorShapeManagerV1>,
+    cursor_shape_device: Option<WpCursorShapeDeviceV1>,
 }
 
 impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
@@ -126,8 +196,12 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
         _: &Connection,
         qh: &QueueHandle<Self>,
     ) {
-        if let wl_registry::Event::Global {
-            name, interface, ..
+        if let wl_registry::Event::GlobalRemove { name } = event {
+            state.remove_output(name);
+        } else if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
         } = event
         {
             match &interface[..] {
@@ -150,12 +224,36 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                     if state.wm_base.is_some() && state.xdg_surface.is_none() {
                         state.init_xdg_surface(qh);
                     }
+                    state.init_content_type(qh);
+                    state.init_viewport(qh);
+                }
+                "wl_subcompositor" => {
+                    state.subcompositor =
+                        Some(registry.bind::<WlSubcompositor, _, _>(name, 1, qh, ()));
+                }
+                "wp_viewporter" => {
+                    state.viewporter = Some(registry.bind::<WpViewporter, _, _>(name, 1, qh, ()));
+                    state.init_viewport(qh);
                 }
                 "wl_shm" => {
                     state.wl_shm = Some(registry.bind::<wl_shm::WlShm, _, _>(name, 1, qh, ()));
                 }
                 "wl_seat" => {
-                    registry.bind::<wl_seat::WlSeat, _, _>(name, 1, qh, ());
+                    // v10 for the compositor's own key repeat.
+                    registry.bind::<wl_seat::WlSeat, _, _>(name, version.min(10), qh, ());
+                }
+                "wl_output" => {
+                    let output = registry.bind::<WlOutput, _, _>(name, version.min(4), qh, ());
+                    state.add_output(name, output, qh);
+                }
+                "zxdg_output_manager_v1" => {
+                    state.xdg_output_manager = Some(registry.bind::<ZxdgOutputManagerV1, _, _>(
+                        name,
+                        version.min(3),
+                        qh,
+                        (),
+                    ));
+                    state.init_xdg_outputs(qh);
                 }
                 "xdg_wm_base" => {
                     let wm_base = registry.bind::<xdg_wm_base::XdgWmBase, _, _>(name, 1, qh, ());
@@ -168,11 +266,21 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                 "zxdg_decoration_manager_v1" => {
                     state.decoration_manager =
                         Some(registry.bind::<ZxdgDecorationManagerV1, _, _>(name, 1, qh, ()));
+                    state.init_toplevel_decoration(qh);
+                }
+                "wp_cursor_shape_manager_v1" => {
+                    state.cursor_shape_manager =
+                        Some(registry.bind::<WpCursorShapeManagerV1, _, _>(name, 1, qh, ()));
                 }
                 "wp_pointer_warp_v1" => {
                     state.pointer_warp =
                         Some(registry.bind::<WpPointerWarpV1, _, _>(name, 1, qh, ()))
                 }
+                "zwp_relative_pointer_manager_v1" => {
+                    state.relative_pointer_manager =
+                        Some(registry.bind::<ZwpRelativePointerManagerV1, _, _>(name, 1, qh, ()));
+                    state.init_relative_pointer(qh);
+                }
                 "xdg_toplevel_icon_manager_v1" => {
                     state.toplevel_icon_manager =
                         Some(registry.bind::<XdgToplevelIconManagerV1, _, _>(name, 1, qh, ()))
@@ -181,6 +289,11 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                     state.fifo_manager =
                         Some(registry.bind::<WpFifoManagerV1, _, _>(name, 1, qh, ()));
                 }
+                "wp_content_type_manager_v1" => {
+                    state.content_type_manager =
+                        Some(registry.bind::<WpContentTypeManagerV1, _, _>(name, 1, qh, ()));
+                    state.init_content_type(qh);
+                }
                 _ => {
                     println!("[unhandled] {}", &interface[..]);
                 }
@@ -190,10 +303,14 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
 }
 
 pub struct WaylandWindow {
+    connection: Connection,
     state: WaylandState,
     event_queue: EventQueue<WaylandState>,
+    // Held while SDL_WaitEvent sleeps, so nobody else reads our events off the socket first.
+    read_guard: Option<ReadEventsGuard>,
     fake_surface: SDL_Surface,
     gl_attrs: [i32; 32],
+    video_modes: VideoModes,
 }
 
 impl WaylandWindow {
@@ -226,16 +343,27 @@ impl WaylandWindow {
             configured: false,
             native_display: Some(display),
             running: true,
+            window_size: (640, 480),
             ..Default::default()
         };
-        state.keys.resize(SDLKey_SDLK_LAST as usize, 0);
+        event_queue.roundtrip(&mut state).unwrap();
+        // The second one is for the outputs to tell us about themselves, since the game likes
+        // asking about the display before it sets a video mode.
         event_queue.roundtrip(&mut state).unwrap();
 
+        // By now we've seen every global, so if there's no decoration manager there never will be.
+        if state.decoration_manager.is_none() {
+            state.enable_csd(&qhandle);
+        }
+
         Self {
+            connection: conn,
             state,
             event_queue,
+            read_guard: None,
             fake_surface,
             gl_attrs: [0; _],
+            video_modes: VideoModes::new(),
         }
     }
 
@@ -322,19 +450,167 @@ impl WaylandState {
 
         let xdg_surface = wm_base.get_xdg_surface(compositor_surface, qh, ());
         let toplevel = xdg_surface.get_toplevel(qh, ());
+        toplevel.set_app_id(config::app_id());
+
+        compositor_surface.commit();
 
-        if let Some(decoration_manager) = self.decoration_manager.as_mut() {
-            let toplevel_decoration = decoration_manager.get_toplevel_decoration(&toplevel, qh, ());
+        self.xdg_surface = Some(xdg_surface);
+        self.xdg_top_level = Some(toplevel);
+        self.init_toplevel_decoration(qh);
+        self.apply_icon(qh);
+    }
+
+    // Asks for server-side decorations. If we don't get them, the decoration's configure event
+    // turns on our own.
+    fn init_toplevel_decoration(&mut self, qh: &QueueHandle<WaylandState>) {
+        if self.toplevel_decoration.is_some() {
+            return;
+        }
+        if let (Some(decoration_manager), Some(toplevel)) = (
+            self.decoration_manager.as_ref(),
+            self.xdg_top_level.as_ref(),
+        ) {
+            let toplevel_decoration = decoration_manager.get_toplevel_decoration(toplevel, qh, ());
 
             toplevel_decoration.set_mode(Mode::ServerSide);
 
             self.toplevel_decoration = Some(toplevel_decoration);
         }
+    }
 
-        compositor_surface.commit();
+    // Tells the compositor this is a game, so it can do things like turn off the screensaver or
+    // skip latency-adding effects. Needs both the surface and the manager, which can arrive in
+    // either order.
+    fn init_content_type(&mut self, qh: &QueueHandle<WaylandState>) {
+        if self.content_type.is_some() {
+            return;
+        }
+        if let (Some(manager), Some(surface)) = (
+            self.content_type_manager.as_ref(),
+            self.compositor_surface.as_ref(),
+        ) {
+            let content_type = manager.get_surface_content_type(surface, qh, ());
+            content_type.set_content_type(wp_content_type_v1::Type::Game);
+            self.content_type = Some(content_type);
+        }
+    }
 
-        self.xdg_surface = Some(xdg_surface);
-        self.xdg_top_level = Some(toplevel);
+    fn init_viewport(&mut self, qh: &QueueHandle<WaylandState>) {
+        if self.viewport.is_some() {
+            return;
+        }
+        if let (Some(viewporter), Some(surface)) =
+            (self.viewporter.as_ref(), self.compositor_surface.as_ref())
+        {
+            self.viewport = Some(viewporter.get_viewport(surface, qh, ()));
+        }
+    }
+
+    /// The size the game shows up at, which is only different from what it asked for when
+    /// maximized.
+    fn shown_size(&self) -> (i32, i32) {
+        self.maximized.unwrap_or(self.window_size)
+    }
+
+    /// Surface coordinates back in the game's, for when it's scaled up.
+    fn surface_to_game(&self, x: f64, y: f64) -> (f64, f64) {
+        let (shown_w, shown_h) = self.shown_size();
+        let (w, h) = self.window_size;
+        (x * w as f64 / shown_w as f64, y * h as f64 / shown_h as f64)
+    }
+
+    fn game_to_surface(&self, x: f64, y: f64) -> (f64, f64) {
+        let (shown_w, shown_h) = self.shown_size();
+        let (w, h) = self.window_size;
+        (x * shown_w as f64 / w as f64, y * shown_h as f64 / h as f64)
+    }
+
+    /// Follows the toplevel's maximized state. `size` is the whole window's, title bar included,
+    /// and None once it's back to normal.
+    fn set_maximized(&mut self, size: Option<(i32, i32)>, qh: &QueueHandle<WaylandState>) {
+        let Some(viewport) = self.viewport.as_ref() else {
+            return;
+        };
+        let size = size.map(|(w, h)| (w, (h - self.title_height()).max(1)));
+        if size == self.maximized {
+            return;
+        }
+        match size {
+            Some((w, h)) => viewport.set_destination(w, h),
+            None => viewport.set_destination(-1, -1),
+        }
+        self.maximized = size;
+        self.update_window_geometry();
+        self.draw_decorations(qh);
+    }
+
+    // Once there's a pointer, so the mouse's own motion can count for SDL_GetRelativeMouseState.
+    fn init_relative_pointer(&mut self, qh: &QueueHandle<WaylandState>) {
+        if self.relative_pointer.is_some() {
+            return;
+        }
+        if let (Some(manager), Some(pointer)) = (
+            self.relative_pointer_manager.as_ref(),
+            self.pointer.as_ref(),
+        ) {
+            self.relative_pointer = Some(manager.get_relative_pointer(pointer, qh, ()));
+        }
+    }
+
+    /// Sends a key or button press on its way, once the remap table turned it into `input`.
+    /// `keycode` and `unicode` are from the key that was actually pressed, if it was one.
+    fn send_input(&mut self, input: Input, pressed: bool, keycode: u32, unicode: u16) {
+        match input {
+            Input::Key(key) => {
+                let keysym = self.input.key(key, pressed, keycode, unicode);
+                self.events.push(events::key_event(pressed, keysym));
+                if pressed {
+                    self.key_repeat.press(keycode, keysym);
+                } else {
+                    self.key_repeat.release(keycode);
+                }
+            }
+            Input::Button(button) => {
+                let event = self.input.button(button, pressed);
+                self.events.push(event);
+            }
+        }
+    }
+
+    fn send_button(&mut self, button: u8, pressed: bool) {
+        let input = if pressed {
+            self.remap
+                .press(Input::Button(button), self.input.modifiers().get())
+        } else {
+            self.remap.release(Input::Button(button))
+        };
+        self.send_input(input, pressed, 0, 0);
+    }
+
+    fn send_touch(&mut self, actions: Vec<TouchAction>) {
+        for action in actions {
+            match action {
+                TouchAction::Motion(x, y) => {
+                    let event = self.input.motion(x, y);
+                    self.events.push(event);
+                }
+                TouchAction::Button(button, pressed) => self.send_button(button, pressed),
+            }
+        }
+    }
+
+    // Each wheel click is a press and release right after another, like SDL 1.2 does it.
+    fn send_wheel(&mut self) {
+        let clicks = self.wheel.frame();
+        let button = if clicks < 0 {
+            SDL_BUTTON_WHEELUP
+        } else {
+            SDL_BUTTON_WHEELDOWN
+        };
+        for _ in 0..clicks.abs() {
+            self.send_button(button, true);
+            self.send_button(button, false);
+        }
     }
 
     pub unsafe fn panic_on_error(&self, reason: &str, err: EGLBoolean) {
@@ -368,111 +644,40 @@ impl Window for WaylandWindow {
         return null();
         // return Box::leak(Box::new(CString::new("")));
     }
-    fn get_key_state(&mut self, numkeys: *mut i32) -> *mut u8 {
-        if numkeys != null_mut() {
-            unsafe {
-                *numkeys = SDLKey_SDLK_LAST as i32;
-            }
+    fn pump_events(&mut self) {
+        self.event_loop();
+        self.joystick_update();
+        if let Some(keysym) = self.state.key_repeat.check() {
+            self.state.events.push(events::key_event(true, keysym));
         }
-        return self.state.keys.as_mut_ptr() as *mut u8;
-    }
 
-    fn get_mouse_state(&mut self, x: *mut i32, y: *mut i32) -> u8 {
-        unsafe {
-            *x = self.state.last_pointer_x as i32;
-            *y = self.state.last_pointer_y as i32;
+        if !self.state.running && self.state.events.is_empty() {
+            self.state.quit_attempts += 1;
+            self.state.events.push(events::quit_event());
+            if self.state.quit_attempts >= 10 {
+                println!(
+                    "WARNING: had to exit manually because the application didn't respond to SDL_QUIT."
+                );
+                exit(0);
+            }
         }
-        return 0;
     }
-
-    fn poll_event(&mut self, event: *mut type_defs::SDL_Event) -> i32 {
-        self.event_loop();
-
-        unsafe {
-            if self.state.pointer_events.len() >= 1 {
-                use wayland_client::protocol::wl_pointer::Event;
-                if let Some(ev) = self.state.pointer_events.pop() {
-                    match ev {
-                        Event::Motion {
-                            time,
-                            surface_x,
-                            surface_y,
-                        } => {
-                            (*event).motion.type_ = SDL_EventType::SDL_MOUSEMOTION as u8;
-                            (*event).motion.state = 0;
-                            (*event).motion.x = surface_x as u16;
-                            (*event).motion.y = surface_y as u16;
-                            (*event).motion.xrel = (surface_x - self.state.last_pointer_x) as i16;
-                            (*event).motion.yrel = (surface_y - self.state.last_pointer_y) as i16;
-                            self.state.last_pointer_x = surface_x;
-                            self.state.last_pointer_y = surface_y;
-                        }
-                        Event::Button {
-                            serial,
-                            time,
-                            button,
-                            state,
-                        } => {
-                            if let wayland_client::WEnum::Value(a) = state {
-                                match a {
-                                    wayland_client::protocol::wl_pointer::ButtonState::Released => {
-                                        (*event).button.type_ =
-                                            SDL_EventType::SDL_MOUSEBUTTONUP as u8;
-                                        (*event).button.state = 0;
-                                    }
-                                    wayland_client::protocol::wl_pointer::ButtonState::Pressed => {
-                                        (*event).button.type_ =
-                                            SDL_EventType::SDL_MOUSEBUTTONDOWN as u8;
-                                        (*event).button.state = 1;
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            (*event).button.button = (button - 271) as u8;
-                            (*event).button.x = self.state.last_pointer_x as u16;
-                            (*event).button.y = self.state.last_pointer_y as u16;
-                        }
-                        _ => {}
-                    }
-                }
-                return 1;
-            } else if self.state.active_keysyms.len() >= 1 {
-                if let Some(ev) = self.state.active_keysyms.pop() {
-                    if ev.0 == 0 {
-                        (*event).key.type_ = SDL_EventType::SDL_KEYUP as u8;
-                    } else {
-                        (*event).key.type_ = SDL_EventType::SDL_KEYDOWN as u8;
-                    }
-                    (*event).key.which = 0;
-                    (*event).key.state = ev.0 as u8;
-                    (*event).key.keysym = ev.1;
-                }
-                return 1;
-            // } else if self.state.resize_happened {
-            //     if !self.state.resize_cycle {
-            //         (*event).resize.type_ = SDL_EventType::SDL_VIDEORESIZE as u8;
-            //         (*event).resize.w = self.state.resized_x;
-            //         (*event).resize.h = self.state.resized_y;
-            //         self.state.resize_happened = false;
-            //         self.state.resize_cycle = true;
-            //         return 1;
-            //     } else {
-            //         self.state.resize_cycle = false;
-            //     }
-            } else if !self.state.running {
-                self.state.quit_attempts += 1;
-                (*event).quit.type_ = SDL_EventType::SDL_QUIT as u8;
-                if self.state.quit_attempts >= 10 {
-                    println!(
-                        "WARNING: had to exit manually because the application didn't respond to SDL_QUIT."
-                    );
-                    exit(0);
-                }
-                return 1;
-            }
+    fn input_fds(&mut self) -> Vec<RawFd> {
+        vec![self.connection.backend().poll_fd().as_raw_fd()]
+    }
+    fn prepare_wait(&mut self) -> bool {
+        // Dispatching queues up requests too, like pongs and configure acks, and the compositor
+        // would wait on those forever while we wait on it.
+        if let Err(err) = self.connection.flush() {
+            println!("couldn't flush the wayland connection: {}", err);
         }
-
-        return 0;
+        // None means there's events queued up already, which the next pump dispatches.
+        self.read_guard = self.event_queue.prepare_read();
+        self.read_guard.is_some()
+    }
+    fn finish_wait(&mut self) {
+        // Cancels the read, pump_events reads for real.
+        self.read_guard = None;
     }
 
     fn set_video_mode(
@@ -486,12 +691,12 @@ impl Window for WaylandWindow {
 
         self.fake_surface.w = width;
         self.fake_surface.h = height;
-        self.fake_surface.clip_rect.w = height;
-        self.fake_surface.clip_rect.h = height;
+        self.fake_surface.clip_rect.w = width as u16;
+        self.fake_surface.clip_rect.h = height as u16;
 
-        let xdg_top_level = self.state.xdg_top_level();
-        xdg_top_level.set_min_size(width, height);
-        xdg_top_level.set_max_size(width, height);
+        self.state.window_size = (width, height);
+        self.state.update_window_geometry();
+        self.state.draw_decorations(&self.event_queue.handle());
         self.state.egl_surface().resize(width, height, 0, 0);
 
         // TODO: find out how to make fullscreen work
@@ -512,21 +717,22 @@ impl Window for WaylandWindow {
         if let Some(pointer) = &self.state.pointer {
             if let Some(pointer_warp) = &self.state.pointer_warp {
                 let surface = self.state.compositor_surface();
-                pointer_warp.warp_pointer(
-                    surface,
-                    &pointer,
-                    x as f64,
-                    y as f64,
-                    self.state.pointer_serial,
-                );
+                let (x, y) = self.state.game_to_surface(x as f64, y as f64);
+                pointer_warp.warp_pointer(surface, &pointer, x, y, self.state.pointer_serial);
             }
         }
     }
     fn wm_set_caption(&mut self, title: &str, icon: &str) {
         self.state.xdg_top_level().set_title(title.into());
+        self.state.title = title.into();
+        self.state.draw_decorations(&self.event_queue.handle());
 
         // we set the icon in xdg.rs
     }
+    fn wm_set_icon(&mut self, icon: RgbaImage) {
+        self.state.icon = Some(icon);
+        self.state.apply_icon(&self.event_queue.handle());
+    }
 
     fn egl(&self) -> &EGL {
         self.state.egl.as_ref().unwrap()
@@ -545,6 +751,41 @@ impl Window for WaylandWindow {
     fn egl_surface(&self) -> EGLSurface {
         self.state.native_surface
     }
+
+    fn video_modes(&mut self) -> &mut VideoModes {
+        &mut self.video_modes
+    }
+    fn key_repeat(&mut self) -> &mut KeyRepeat {
+        &mut self.state.key_repeat
+    }
+    fn input(&mut self) -> &mut InputState {
+        &mut self.state.input
+    }
+    fn unicode(&mut self) -> &mut Unicode {
+        &mut self.state.unicode
+    }
+    fn joysticks(&mut self) -> &mut Joysticks {
+        &mut self.state.joysticks
+    }
+    fn events(&mut self) -> &mut Queue {
+        &mut self.state.events
+    }
+    fn display_size(&mut self) -> Option<(i32, i32)> {
+        self.state.current_output().and_then(Output::size)
+    }
+    fn display_modes(&mut self) -> Vec<(i32, i32)> {
+        match self.state.current_output() {
+            Some(output) => match output.size() {
+                Some(size) => video::fitting_modes(size, &output.modes()),
+                None => vec![],
+            },
+            None => vec![],
+        }
+    }
+    fn surface_size(&mut self) -> (i32, i32) {
+        (self.fake_surface.w, self.fake_surface.h)
+    }
+
     fn gl_swap_buffers(&mut self) {
         if let Some(fifo) = self.state.fifo.as_ref() {
             fifo.wait_barrier();
@@ -567,7 +808,10 @@ delegate_noop!(WaylandState: ignore WlShm);
 delegate_noop!(WaylandState: ignore WlRegion);
 delegate_noop!(WaylandState: ignore WlBuffer);
 delegate_noop!(WaylandState: ignore WlShmPool);
-delegate_noop!(WaylandState: ignore WlSurface);
 delegate_noop!(WaylandState: ignore ZxdgDecorationManagerV1);
-delegate_noop!(WaylandState: ignore ZxdgToplevelDecorationV1);
 delegate_noop!(WaylandState: ignore WpPointerWarpV1);
+delegate_noop!(WaylandState: ignore ZwpRelativePointerManagerV1);
+delegate_noop!(WaylandState: ignore WpContentTypeManagerV1);
+delegate_noop!(WaylandState: ignore WpContentTypeV1);
+delegate_noop!(WaylandState: ignore WpViewporter);
+delegate_noop!(WaylandState: ignore WpViewport);
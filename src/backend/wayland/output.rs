@@ -0,0 +1,207 @@
+use wayland_client::{
+    Dispatch, Proxy, QueueHandle, WEnum,
+    protocol::{
+        wl_output::{self, Transform, WlOutput},
+        wl_surface::{self, WlSurface},
+    },
+};
+use wayland_protocols::xdg::xdg_output::zv1::client::{
+    zxdg_output_manager_v1::ZxdgOutputManagerV1,
+    zxdg_output_v1::{self, ZxdgOutputV1},
+};
+
+use crate::backend::wayland::WaylandState;
+
+pub struct Output {
+    global_name: u32,
+    output: WlOutput,
+    xdg_output: Option<ZxdgOutputV1>,
+    // In physical pixels, like wl_output sends them.
+    modes: Vec<(i32, i32)>,
+    current_mode: Option<(i32, i32)>,
+    rotated: bool,
+    scale: i32,
+    // From xdg_output, which takes fractional scaling into account where wl_output doesn't.
+    logical_size: Option<(i32, i32)>,
+}
+
+impl Output {
+    fn physical_size(&self) -> Option<(i32, i32)> {
+        let (w, h) = self.current_mode?;
+        Some(if self.rotated { (h, w) } else { (w, h) })
+    }
+
+    /// Size of the output in surface coordinates, which is what the game's window is sized in.
+    pub fn size(&self) -> Option<(i32, i32)> {
+        self.logical_size.or_else(|| {
+            self.physical_size()
+                .map(|(w, h)| (w / self.scale.max(1), h / self.scale.max(1)))
+        })
+    }
+
+    /// The modes the output advertised, converted to surface coordinates.
+    pub fn modes(&self) -> Vec<(i32, i32)> {
+        let (Some(physical), Some(logical)) = (self.physical_size(), self.size()) else {
+            return vec![];
+        };
+        let factor = logical.0 as f64 / physical.0 as f64;
+        self.modes
+            .iter()
+            .map(|&(w, h)| if self.rotated { (h, w) } else { (w, h) })
+            .map(|(w, h)| {
+                (
+                    (w as f64 * factor).round() as i32,
+                    (h as f64 * factor).round() as i32,
+                )
+            })
+            .collect()
+    }
+}
+
+impl WaylandState {
+    pub fn add_output(&mut self, global_name: u32, output: WlOutput, qh: &QueueHandle<Self>) {
+        let xdg_output = self
+            .xdg_output_manager
+            .as_ref()
+            .map(|manager| manager.get_xdg_output(&output, qh, ()));
+        self.outputs.push(Output {
+            global_name,
+            output,
+            xdg_output,
+            modes: vec![],
+            current_mode: None,
+            rotated: false,
+            scale: 1,
+            logical_size: None,
+        });
+    }
+
+    pub fn remove_output(&mut self, global_name: u32) {
+        if let Some(idx) = self
+            .outputs
+            .iter()
+            .position(|o| o.global_name == global_name)
+        {
+            let output = self.outputs.remove(idx);
+            self.surface_outputs.retain(|o| *o != output.output);
+            if let Some(xdg_output) = output.xdg_output {
+                xdg_output.destroy();
+            }
+            if output.output.version() >= 3 {
+                output.output.release();
+            }
+        }
+    }
+
+    /// Called once the manager is bound, for any outputs that showed up before it did.
+    pub fn init_xdg_outputs(&mut self, qh: &QueueHandle<Self>) {
+        if let Some(manager) = self.xdg_output_manager.as_ref() {
+            for output in self.outputs.iter_mut() {
+                if output.xdg_output.is_none() {
+                    output.xdg_output = Some(manager.get_xdg_output(&output.output, qh, ()));
+                }
+            }
+        }
+    }
+
+    /// The output the window is on, or failing that, the first one we know of.
+    pub fn current_output(&self) -> Option<&Output> {
+        self.surface_outputs
+            .first()
+            .and_then(|current| self.outputs.iter().find(|o| o.output == *current))
+            .or(self.outputs.first())
+    }
+}
+
+impl Dispatch<WlOutput, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlOutput,
+        event: <WlOutput as wayland_client::Proxy>::Event,
+        data: &(),
+        conn: &wayland_client::Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.iter_mut().find(|o| o.output == *proxy) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry { transform, .. } => {
+                output.rotated = matches!(
+                    transform,
+                    WEnum::Value(
+                        Transform::_90
+                            | Transform::_270
+                            | Transform::Flipped90
+                            | Transform::Flipped270
+                    )
+                );
+            }
+            wl_output::Event::Mode {
+                flags,
+                width,
+                height,
+                refresh,
+            } => {
+                if !output.modes.contains(&(width, height)) {
+                    output.modes.push((width, height));
+                }
+                if let WEnum::Value(flags) = flags
+                    && flags.contains(wl_output::Mode::Current)
+                {
+                    output.current_mode = Some((width, height));
+                }
+            }
+            wl_output::Event::Scale { factor } => {
+                output.scale = factor;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZxdgOutputV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &ZxdgOutputV1,
+        event: <ZxdgOutputV1 as wayland_client::Proxy>::Event,
+        data: &(),
+        conn: &wayland_client::Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        if let zxdg_output_v1::Event::LogicalSize { width, height } = event
+            && let Some(output) = state
+                .outputs
+                .iter_mut()
+                .find(|o| o.xdg_output.as_ref() == Some(proxy))
+        {
+            output.logical_size = Some((width, height));
+        }
+    }
+}
+
+// Tells us which output(s) the window is on.
+impl Dispatch<WlSurface, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlSurface,
+        event: <WlSurface as wayland_client::Proxy>::Event,
+        data: &(),
+        conn: &wayland_client::Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        if state.compositor_surface.as_ref() != Some(proxy) {
+            return;
+        }
+        match event {
+            wl_surface::Event::Enter { output } if !state.surface_outputs.contains(&output) => {
+                state.surface_outputs.push(output);
+            }
+            wl_surface::Event::Leave { output } => {
+                state.surface_outputs.retain(|o| *o != output);
+            }
+            _ => {}
+        }
+    }
+}
+wayland_client::delegate_noop!(WaylandState: ignore ZxdgOutputManagerV1);
@@ -1,6 +1,13 @@
-use wayland_client::{Dispatch, protocol::wl_pointer::WlPointer};
+use wayland_client::{
+    Dispatch, Proxy, WEnum,
+    protocol::wl_pointer::{Axis, ButtonState, WlPointer},
+};
 
-use crate::backend::wayland::WaylandState;
+use wayland_protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1::{
+    self, ZwpRelativePointerV1,
+};
+
+use crate::backend::{input::sdl_button, wayland::WaylandState};
 use wayland_client::protocol::wl_pointer::Event;
 
 impl Dispatch<WlPointer, ()> for WaylandState {
@@ -22,13 +29,24 @@ impl Dispatch<WlPointer, ()> for WaylandState {
                 surface_x,
                 surface_y,
             } => {
-                state.last_pointer_x = surface_x;
-                state.last_pointer_y = surface_y;
                 state.pointer_serial = serial;
+                state.pointer_on_decorations = state
+                    .decorations
+                    .as_ref()
+                    .is_some_and(|d| d.is_surface(&surface));
+                if state.pointer_on_decorations {
+                    state.decoration_motion(surface_x);
+                    state.show_decoration_cursor(serial, qhandle);
+                    return;
+                }
+
+                let (x, y) = state.surface_to_game(surface_x, surface_y);
+                state.input.set_position(x, y);
 
                 proxy.set_cursor(serial, None, 0, 0);
             }
             Event::Leave { serial, surface } => {
+                state.pointer_on_decorations = false;
                 proxy.set_cursor(serial, Some(&surface), 0, 0);
             }
             Event::Motion {
@@ -36,18 +54,81 @@ impl Dispatch<WlPointer, ()> for WaylandState {
                 surface_x,
                 surface_y,
             } => {
-                state.pointer_events.push(event);
+                if state.pointer_on_decorations {
+                    state.decoration_motion(surface_x);
+                    return;
+                }
+                let (x, y) = state.surface_to_game(surface_x, surface_y);
+                let event = state.input.pointer_motion(x, y);
+                state.events.push(event);
             }
             Event::Button {
                 serial,
                 time,
                 button,
-                state: _,
+                state: button_state,
             } => {
-                state.pointer_events.push(event);
+                if state.pointer_on_decorations {
+                    if button_state == WEnum::Value(ButtonState::Pressed) {
+                        state.decoration_button(button, serial);
+                    }
+                    return;
+                }
+                let (Some(button), WEnum::Value(button_state)) = (sdl_button(button), button_state)
+                else {
+                    return;
+                };
+                state.send_button(button, button_state == ButtonState::Pressed);
+            }
+            // Only the vertical wheel, SDL 1.2 has no buttons for the horizontal one.
+            Event::Axis {
+                axis: WEnum::Value(Axis::VerticalScroll),
+                value,
+                ..
+            } if !state.pointer_on_decorations => {
+                state.wheel.smooth(value);
+                // Before v5 there are no frames, every event stands on its own.
+                if proxy.version() < 5 {
+                    state.send_wheel();
+                }
+            }
+            Event::AxisDiscrete {
+                axis: WEnum::Value(Axis::VerticalScroll),
+                discrete,
+            } if !state.pointer_on_decorations => {
+                state.wheel.v120(discrete as f64 * 120.0);
+            }
+            Event::AxisValue120 {
+                axis: WEnum::Value(Axis::VerticalScroll),
+                value120,
+            } if !state.pointer_on_decorations => {
+                state.wheel.v120(value120 as f64);
+            }
+            Event::Frame => {
+                state.send_wheel();
             }
-
             _ => {}
         }
     }
 }
+
+impl Dispatch<ZwpRelativePointerV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpRelativePointerV1,
+        event: <ZwpRelativePointerV1 as Proxy>::Event,
+        data: &(),
+        conn: &wayland_client::Connection,
+        qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let zwp_relative_pointer_v1::Event::RelativeMotion {
+            dx_unaccel,
+            dy_unaccel,
+            ..
+        } = event
+            && !state.pointer_on_decorations
+        {
+            state.input.raw_motion(dx_unaccel, dy_unaccel);
+        }
+    }
+}
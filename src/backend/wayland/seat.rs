@@ -1,5 +1,5 @@
 use wayland_client::{
-    Connection, Dispatch, QueueHandle, WEnum,
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
     protocol::wl_seat::{self, WlSeat},
 };
 
@@ -14,7 +14,9 @@ impl Dispatch<WlSeat, ()> for WaylandState {
         conn: &Connection,
         qhandle: &QueueHandle<Self>,
     ) {
-        let _ = event;
+        if state.seat.is_none() {
+            state.seat = Some(seat.clone());
+        }
         if let wl_seat::Event::Capabilities {
             capabilities: WEnum::Value(capabilities),
         } = event
@@ -23,7 +25,21 @@ impl Dispatch<WlSeat, ()> for WaylandState {
                 seat.get_keyboard(qhandle, ());
             }
             if capabilities.contains(wl_seat::Capability::Pointer) {
-                seat.get_pointer(qhandle, ());
+                state.pointer = Some(seat.get_pointer(qhandle, ()));
+                state.init_relative_pointer(qhandle);
+            }
+            // Capabilities get sent again whenever a device comes or goes, and a second wl_touch
+            // would deliver every touch twice.
+            if capabilities.contains(wl_seat::Capability::Touch) {
+                if state.wl_touch.is_none() {
+                    state.wl_touch = Some(seat.get_touch(qhandle, ()));
+                }
+            } else if let Some(touch) = state.wl_touch.take() {
+                if touch.version() >= 3 {
+                    touch.release();
+                }
+                let actions = state.touch.cancel();
+                state.send_touch(actions);
             }
         }
     }
@@ -0,0 +1,55 @@
+use wayland_client::{
+    Dispatch,
+    protocol::wl_touch::{Event, WlTouch},
+};
+
+use crate::backend::wayland::WaylandState;
+
+impl Dispatch<WlTouch, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlTouch,
+        event: <WlTouch as wayland_client::Proxy>::Event,
+        data: &(),
+        conn: &wayland_client::Connection,
+        qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            Event::Down {
+                serial,
+                time,
+                surface,
+                id,
+                x,
+                y,
+            } => {
+                // Touching our title bar doesn't count.
+                if state
+                    .decorations
+                    .as_ref()
+                    .is_some_and(|d| d.is_surface(&surface))
+                {
+                    return;
+                }
+                let (x, y) = state.surface_to_game(x, y);
+                let actions = state.touch.down(id, x as i32, y as i32);
+                state.send_touch(actions);
+            }
+            Event::Motion { time, id, x, y } => {
+                let (x, y) = state.surface_to_game(x, y);
+                if let Some(action) = state.touch.motion(id, x as i32, y as i32) {
+                    state.send_touch(vec![action]);
+                }
+            }
+            Event::Up { serial, time, id } => {
+                let actions = state.touch.up(id);
+                state.send_touch(actions);
+            }
+            Event::Cancel => {
+                let actions = state.touch.cancel();
+                state.send_touch(actions);
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,21 +1,21 @@
 use std::{env::current_dir, io::Write, os::fd::AsFd};
 
-use image::ImageReader;
+use image::{ImageReader, imageops};
 use wayland_client::{Dispatch, Proxy, delegate_noop, protocol::wl_shm};
 use wayland_protocols::xdg::{
     shell::client::{
         xdg_surface::{self, XdgSurface},
-        xdg_toplevel::XdgToplevel,
+        xdg_toplevel::{self, XdgToplevel},
         xdg_wm_base::{self, XdgWmBase},
     },
     toplevel_icon::v1::client::{
-        xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1,
+        xdg_toplevel_icon_manager_v1::{self, XdgToplevelIconManagerV1},
         xdg_toplevel_icon_v1::XdgToplevelIconV1,
     },
 };
 
 use crate::{
-    backend::wayland::WaylandState,
+    backend::{icon, wayland::WaylandState},
     egl::{EGL, EGLWindowType, NativeDisplayType, NativeWindowType},
 };
 
@@ -83,6 +83,14 @@ impl Dispatch<XdgToplevel, ()> for WaylandState {
                 height,
                 states,
             } => {
+                let maximized = states
+                    .chunks_exact(4)
+                    .map(|s| u32::from_ne_bytes([s[0], s[1], s[2], s[3]]))
+                    .any(|s| s == xdg_toplevel::State::Maximized as u32);
+                // 0x0 leaves the size up to us, which with a game is the one it asked for.
+                let size = (maximized && width > 0 && height > 0).then_some((width, height));
+                state.set_maximized(size, qhandle);
+
                 // if !state.resize_cycle {
                 //     println!("resized to {} {}", width, height);
                 //     state.egl_surface().resize(width, height, 0, 0);
@@ -115,6 +123,86 @@ impl Dispatch<XdgWmBase, ()> for WaylandState {
     }
 }
 
+// Used when the compositor doesn't say which sizes it would like.
+const DEFAULT_ICON_SIZES: [i32; 4] = [16, 32, 48, 64];
+
+impl WaylandState {
+    /// Hands the compositor the game's icon, or our stand-in if the game never set one.
+    pub fn apply_icon(&mut self, qhandle: &wayland_client::QueueHandle<Self>) {
+        let (Some(manager), Some(xdg_top_level), Some(wl_shm)) = (
+            self.toplevel_icon_manager.as_ref(),
+            self.xdg_top_level.as_ref(),
+            self.wl_shm.as_ref(),
+        ) else {
+            return;
+        };
+        let Some(img) = self.icon.clone().or_else(fallback_icon) else {
+            return;
+        };
+
+        let sizes: &[i32] = if self.icon_sizes.is_empty() {
+            &DEFAULT_ICON_SIZES
+        } else {
+            &self.icon_sizes
+        };
+        let mut file = tempfile::tempfile().unwrap();
+        let mut offsets = vec![];
+        let mut len = 0;
+        for &size in sizes {
+            let data = icon::to_argb8888(&icon::scaled(&img, size as u32));
+            file.write_all(&data).unwrap();
+            offsets.push((size, len));
+            len += data.len() as i32;
+        }
+
+        let icon = manager.create_icon(qhandle, ());
+        let pool = wl_shm.create_pool(file.as_fd(), len, qhandle, ());
+        for (size, offset) in offsets {
+            let buffer = pool.create_buffer(
+                offset,
+                size,
+                size,
+                size * 4,
+                wl_shm::Format::Argb8888,
+                qhandle,
+                (),
+            );
+            icon.add_buffer(&buffer, 1);
+        }
+        pool.destroy();
+
+        manager.set_icon(xdg_top_level, Some(&icon));
+        if let Some(old) = self.toplevel_icon.replace(icon) {
+            old.destroy();
+        }
+    }
+}
+
+fn fallback_icon() -> Option<image::RgbaImage> {
+    // The original Linux version of the game didn't actually set the icon it seems. So
+    // we fudge something by loading the lives image and cropping it, since that's roughly what it is
+    // on Windows.
+    //
+    // TODO: Really? It didn't? I'm not finding anything in the install directory and
+    // when the game sets the 'icon path' it uses the same string as the title (down to the same pointer).
+    // Maybe get an old Linux VM set up one day to confirm this.
+    match ImageReader::open(current_dir().unwrap().join("textures").join("tuxlife.png"))
+        .map_err(image::ImageError::from)
+        .and_then(|img| img.decode())
+    {
+        Ok(img) => {
+            // Cropping past the edge would panic, and this is all behind the game's C calls.
+            let img = img.to_rgba8();
+            let (w, h) = img.dimensions();
+            Some(imageops::crop_imm(&img, 0, 0, w.min(32), h.min(32)).to_image())
+        }
+        Err(err) => {
+            println!("error setting icon: {}", err);
+            None
+        }
+    }
+}
+
 impl wayland_client::Dispatch<XdgToplevelIconManagerV1, ()> for WaylandState {
     fn event(
         state: &mut Self,
@@ -124,58 +212,16 @@ impl wayland_client::Dispatch<XdgToplevelIconManagerV1, ()> for WaylandState {
         conn: &wayland_client::Connection,
         qhandle: &wayland_client::QueueHandle<Self>,
     ) {
-        if let Some(xdg_top_level) = state.xdg_top_level.as_mut() {
-            if let Some(wl_shm) = state.wl_shm.as_mut() {
-                if let None = state.toplevel_icon {
-                    // The original Linux version of the game didn't actually set the icon it seems. So
-                    // we fudge something by loading the lives image and cropping it, since that's roughly what it is
-                    // on Windows.
-                    //
-                    // TODO: Really? It didn't? I'm not finding anything in the install directory and
-                    // when the game sets the 'icon path' it uses the same string as the title (down to the same pointer).
-                    // Maybe get an old Linux VM set up one day to confirm this.
-
-                    match ImageReader::open(
-                        current_dir().unwrap().join("textures").join("tuxlife.png"),
-                    ) {
-                        Ok(img) => {
-                            let icon = manager.create_icon(&qhandle, ());
-
-                            let mut img = img.decode().unwrap();
-                            let (init_w, init_h) = (32, 32);
-                            img = img.crop(0, 0, init_w, init_h);
-                            // The image's hue is wrong when we end up writing it to the wl_shm.
-                            // TODO: Why
-                            img = img.huerotate(140);
-
-                            let mut file = tempfile::tempfile().unwrap();
-                            file.write(img.as_bytes()).unwrap();
-                            let pool = wl_shm.create_pool(
-                                file.as_fd(),
-                                (init_w * init_h * 4) as i32,
-                                qhandle,
-                                (),
-                            );
-                            let buffer = pool.create_buffer(
-                                0,
-                                init_w as i32,
-                                init_h as i32,
-                                (init_w * 4) as i32,
-                                wl_shm::Format::Argb8888,
-                                qhandle,
-                                (),
-                            );
-
-                            icon.add_buffer(&buffer, 1);
-
-                            manager.set_icon(&xdg_top_level, Some(&icon));
-                        }
-                        Err(err) => {
-                            println!("error setting icon: {}", err);
-                        }
-                    };
-                }
+        match event {
+            xdg_toplevel_icon_manager_v1::Event::IconSize { size }
+                if !state.icon_sizes.contains(&size) =>
+            {
+                state.icon_sizes.push(size);
             }
+            xdg_toplevel_icon_manager_v1::Event::Done => {
+                state.apply_icon(qhandle);
+            }
+            _ => {}
         }
     }
 }
@@ -0,0 +1,127 @@
+// Settings for the translation layer itself, as opposed to anything the game asks for.
+//
+// Everything lives in one ini-style file, `$XDG_CONFIG_HOME/sdl12-shim/config.ini` (or whatever
+// `SDL_SHIM_CONFIG` points at). Keys in `[general]` apply to every game, and a section named after
+// the game's profile overrides them, e.g. `[tuxracer]`. Tables use `[name]` and `[profile.name]`.
+//
+// Any single key can also be overridden from the environment as `SDL_SHIM_<KEY>`, which is handy
+// for one-off runs.
+
+use std::{collections::HashMap, env, fs, path::PathBuf, sync::LazyLock};
+
+static CONFIG: LazyLock<Config> = LazyLock::new(Config::load);
+
+pub fn config() -> &'static Config {
+    &CONFIG
+}
+
+#[derive(Default)]
+pub struct Config {
+    profile: String,
+    sections: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Config {
+    fn load() -> Self {
+        let profile = env::var("SDL_SHIM_PROFILE").unwrap_or_else(|_| executable_name());
+
+        let path = match env::var_os("SDL_SHIM_CONFIG") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => config_dir().map(|dir| dir.join("config.ini")),
+        };
+
+        let mut config = match path.as_ref().map(fs::read_to_string) {
+            Some(Ok(contents)) => Self::parse(&contents),
+            Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => {
+                println!("error reading {}: {}", path.unwrap().display(), err);
+                Self::default()
+            }
+            _ => Self::default(),
+        };
+        config.profile = profile;
+        config
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut sections: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        let mut current = String::from("general");
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = name.trim().to_string();
+                continue;
+            }
+            match line.split_once('=') {
+                Some((key, value)) => sections
+                    .entry(current.clone())
+                    .or_default()
+                    .push((key.trim().to_string(), value.trim().to_string())),
+                None => println!("ignoring config line without '=': {}", line),
+            }
+        }
+
+        Self {
+            profile: String::new(),
+            sections,
+        }
+    }
+
+    /// Looks a setting up in the environment, then the game's profile, then `[general]`.
+    pub fn get(&self, key: &str) -> Option<String> {
+        if let Ok(value) = env::var(format!("SDL_SHIM_{}", key.to_uppercase())) {
+            return Some(value);
+        }
+        [self.profile.as_str(), "general"]
+            .iter()
+            .filter_map(|section| self.sections.get(*section))
+            .find_map(|entries| entries.iter().rev().find(|(k, _)| k == key))
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Every entry in a table, `[name]` first and then `[profile.name]`, so the profile's entries
+    /// come last and win wherever the caller lets later ones override earlier ones.
+    pub fn table(&self, name: &str) -> Vec<(String, String)> {
+        [name.to_string(), format!("{}.{}", self.profile, name)]
+            .iter()
+            .filter_map(|section| self.sections.get(section))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|value| {
+            matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+        })
+    }
+}
+
+/// `$XDG_CONFIG_HOME/sdl12-shim`, where the config file and anything else we read lives.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("sdl12-shim"))
+}
+
+/// The executable's name without any extension, e.g. `tuxracer` for Loki's `tuxracer.dynamic`.
+pub fn executable_name() -> String {
+    env::current_exe()
+        .ok()
+        .as_ref()
+        .and_then(|exe| exe.file_name())
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("sdl-game")
+        .to_string()
+}
+
+/// The identity the game is announced to the compositor under, matching its `.desktop` file.
+pub fn app_id() -> String {
+    config().get("app_id").unwrap_or_else(executable_name)
+}
@@ -7,9 +7,8 @@
 use std::ffi::{CStr, c_char, c_int, c_void};
 
 use crate::{
-    sigsegv_handler,
-    type_defs::{self, SDLMod_KMOD_NONE},
-    window,
+    backend::{icon, queue::EventFilter},
+    sigsegv_handler, type_defs, window, xcb,
 };
 
 unsafe extern "C" {
@@ -25,22 +24,42 @@ pub unsafe extern "C" fn SDL_EnableKeyRepeat(delay: c_int, interval: c_int) -> c
     window().lock().enable_key_repeat(delay, interval)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_EnableUNICODE(enable: c_int) -> c_int {
+    window().lock().enable_unicode(enable)
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_EventState(type_: u8, state: c_int) -> u8 {
+    window().lock().event_state(type_, state)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_GetError() -> *const u8 {
     window().lock().get_error()
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_GetEventFilter() -> EventFilter {
+    window().lock().get_event_filter()
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_GetKeyName(key: type_defs::SDLKey) -> *const c_char {
+    xcb::key_name(key).as_ptr()
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_GetKeyState(numkeys: *mut c_int) -> *mut u8 {
     window().lock().get_key_state(numkeys)
 }
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_GetModState() -> type_defs::SDLMod {
-    return SDLMod_KMOD_NONE;
+    window().lock().get_mod_state()
 }
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_GetMouseState(x: *mut c_int, y: *mut c_int) -> u8 {
     window().lock().get_mouse_state(x, y)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_GetRelativeMouseState(x: *mut c_int, y: *mut c_int) -> u8 {
+    window().lock().get_relative_mouse_state(x, y)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_GetVideoInfo() -> *mut type_defs::SDL_VideoInfo {
     window().lock().get_video_info()
 }
@@ -77,6 +96,10 @@ pub unsafe extern "C" fn SDL_Init(flags: u32) -> c_int {
     window().lock().init(flags)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickClose(joystick: *mut type_defs::SDL_Joystick) {
+    window().lock().joystick_close(joystick)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_JoystickEventState(state: c_int) -> c_int {
     window().lock().joystick_event_state(state)
 }
@@ -88,6 +111,15 @@ pub unsafe extern "C" fn SDL_JoystickGetAxis(
     window().lock().joystick_get_axis(joystick, axis)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickGetBall(
+    joystick: *mut type_defs::SDL_Joystick,
+    ball: c_int,
+    dx: *mut c_int,
+    dy: *mut c_int,
+) -> c_int {
+    window().lock().joystick_get_ball(joystick, ball, dx, dy)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_JoystickGetButton(
     joystick: *mut type_defs::SDL_Joystick,
     button: c_int,
@@ -95,6 +127,17 @@ pub unsafe extern "C" fn SDL_JoystickGetButton(
     window().lock().joystick_get_button(joystick, button)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickGetHat(
+    joystick: *mut type_defs::SDL_Joystick,
+    hat: c_int,
+) -> u8 {
+    window().lock().joystick_get_hat(joystick, hat)
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickIndex(joystick: *mut type_defs::SDL_Joystick) -> c_int {
+    window().lock().joystick_index(joystick)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_JoystickName(index: c_int) -> *const c_char {
     window().lock().joystick_name(index)
 }
@@ -103,26 +146,92 @@ pub unsafe extern "C" fn SDL_JoystickNumAxes(joystick: *mut type_defs::SDL_Joyst
     window().lock().joystick_num_axes(joystick)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickNumBalls(joystick: *mut type_defs::SDL_Joystick) -> c_int {
+    window().lock().joystick_num_balls(joystick)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_JoystickNumButtons(joystick: *mut type_defs::SDL_Joystick) -> c_int {
     window().lock().joystick_num_buttons(joystick)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickNumHats(joystick: *mut type_defs::SDL_Joystick) -> c_int {
+    window().lock().joystick_num_hats(joystick)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_JoystickOpen(index: c_int) -> *mut type_defs::SDL_Joystick {
     window().lock().joystick_open(index)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickOpened(index: c_int) -> c_int {
+    window().lock().joystick_opened(index)
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_JoystickUpdate() {
+    window().lock().joystick_update()
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_ListModes(
+    format: *mut type_defs::SDL_PixelFormat,
+    flags: u32,
+) -> *mut *mut type_defs::SDL_Rect {
+    window().lock().list_modes(format, flags)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_NumJoysticks() -> c_int {
     window().lock().num_joysticks()
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_PeepEvents(
+    events: *mut type_defs::SDL_Event,
+    numevents: c_int,
+    action: c_int,
+    mask: u32,
+) -> c_int {
+    window().lock().peep_events(events, numevents, action, mask)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_PollEvent(event: *mut type_defs::SDL_Event) -> c_int {
+    SDL_PumpEvents();
     window().lock().poll_event(event)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_PumpEvents() {
+    let (filter, unfiltered) = {
+        let mut window = window().lock();
+        window.pump_events();
+        let events = window.events();
+        (events.filter(), events.take_unfiltered())
+    };
+    // The filter might call back into SDL, so the window can't be locked while it runs.
+    let Some(filter) = filter else {
+        return;
+    };
+    let kept: Vec<_> = unfiltered
+        .into_iter()
+        .filter(|event| filter(event) != 0)
+        .collect();
+    let mut window = window().lock();
+    for event in kept {
+        window.events().add(event);
+    }
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_PushEvent(event: *mut type_defs::SDL_Event) -> c_int {
+    window().lock().push_event(event)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_Quit() {
     window().lock().quit()
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_SetEventFilter(filter: EventFilter) {
+    window().lock().set_event_filter(filter)
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_SetModState(modstate: type_defs::SDLMod) {
+    window().lock().set_mod_state(modstate)
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_SetVideoMode(
     width: c_int,
     height: c_int,
@@ -136,6 +245,36 @@ pub unsafe extern "C" fn SDL_ShowCursor(toggle: c_int) -> c_int {
     window().lock().show_cursor(toggle)
 }
 #[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_VideoModeOK(
+    width: c_int,
+    height: c_int,
+    bpp: c_int,
+    flags: u32,
+) -> c_int {
+    window().lock().video_mode_ok(width, height, bpp, flags)
+}
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_WaitEvent(event: *mut type_defs::SDL_Event) -> c_int {
+    loop {
+        SDL_PumpEvents();
+        // Unlocked while we sleep, so other threads can still push events and wake us up.
+        let (mut fds, timeout) = {
+            let mut window = window().lock();
+            if window.poll_event(event) == 1 {
+                return 1;
+            }
+            window.wait_for()
+        };
+        let polled = libc::poll(fds.as_mut_ptr(), fds.len() as _, timeout);
+        let err = std::io::Error::last_os_error();
+        window().lock().finish_wait();
+        if polled < 0 && err.kind() != std::io::ErrorKind::Interrupted {
+            println!("SDL_WaitEvent: {}", err);
+            return 0;
+        }
+    }
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn SDL_WarpMouse(x: u16, y: u16) {
     window().lock().warp_mouse(x, y)
 }
@@ -146,3 +285,10 @@ pub unsafe extern "C" fn SDL_WM_SetCaption(title: *const c_char, icon: *const c_
         &CStr::from_ptr(icon).to_string_lossy(),
     );
 }
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SDL_WM_SetIcon(icon: *mut type_defs::SDL_Surface, mask: *mut u8) {
+    match icon::surface_to_rgba(icon, mask) {
+        Some(icon) => window().lock().wm_set_icon(icon),
+        None => println!("SDL_WM_SetIcon: couldn't read the icon surface"),
+    }
+}
@@ -0,0 +1,47 @@
+// Where virtual joysticks get their time from. Normally that's the wall clock since they were
+// loaded, but with `virtual_joystick_step` every update moves time on by that many milliseconds
+// instead, so a run plays out the same however the game happens to get scheduled.
+
+use std::time::{Duration, Instant};
+
+use crate::config::config;
+
+pub enum Clock {
+    Wall(Instant),
+    Stepped { now: Duration, step: Duration },
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        let step = config().get("virtual_joystick_step");
+        match step.as_deref().map(str::parse) {
+            Some(Ok(ms)) => Self::stepped(Duration::from_millis(ms)),
+            Some(Err(_)) => {
+                println!("virtual_joystick_step should be in milliseconds, going by the clock");
+                Self::Wall(Instant::now())
+            }
+            None => Self::Wall(Instant::now()),
+        }
+    }
+}
+
+impl Clock {
+    pub fn stepped(step: Duration) -> Self {
+        Self::Stepped {
+            now: Duration::ZERO,
+            step,
+        }
+    }
+
+    /// How far into playback we are. Call once per update, a stepped clock moves on afterwards.
+    pub fn tick(&mut self) -> Duration {
+        match self {
+            Self::Wall(start) => start.elapsed(),
+            Self::Stepped { now, step } => {
+                let at = *now;
+                *now += *step;
+                at
+            }
+        }
+    }
+}
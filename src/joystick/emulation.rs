@@ -0,0 +1,259 @@
+// Gamepads pretending to be the keyboard and mouse, for games (or menus) that only take those.
+// It's set up with the `[gamepad_keys]` and `[<game>.gamepad_keys]` tables:
+//
+// button0 = return        a button, numbered like the game sees them
+// axis0- = left           an axis pushed one way or the other
+// hat0up = up             a hat, with up, down, left or right
+// axis2 = mousex          an axis moving the mouse, at `gamepad_mouse_speed` pixels a second
+// button1 = mouse1        mouse buttons are mouse1 to mouse8
+//
+// This goes by every pad, whether or not the game opened it.
+
+use std::{collections::HashSet, time::Instant};
+
+use crate::{
+    backend::remap::Input,
+    config::config,
+    type_defs::{SDL_Event, SDL_EventType, SDL_HAT_DOWN, SDL_HAT_LEFT, SDL_HAT_RIGHT, SDL_HAT_UP},
+};
+
+const DEFAULT_MOUSE_SPEED: f64 = 800.0;
+
+/// What the pads did, as the keyboard and mouse.
+#[derive(PartialEq, Debug)]
+pub enum Emulated {
+    Input(Input, bool),
+    Motion(i32, i32),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Trigger {
+    Button(u8),
+    // The axis, and whether it's pushed positive.
+    Axis(u8, bool),
+    Hat(u8, u8),
+}
+
+enum Bind {
+    Input(Trigger, Input),
+    // An axis, and which of x and y it moves the mouse along.
+    Mouse(u8, usize),
+}
+
+pub struct Emulation {
+    binds: Vec<(Trigger, Input)>,
+    mouse: Vec<(u8, usize)>,
+    // Which binds each pad is holding down, by pad and bind.
+    held: HashSet<(u8, usize)>,
+    speed: f64,
+    // How fast the mouse is going, from -1 to 1, and how far it went that's not a whole pixel.
+    velocity: [f64; 2],
+    partial: [f64; 2],
+    last_frame: Instant,
+    pending: Vec<Emulated>,
+}
+
+impl Default for Emulation {
+    fn default() -> Self {
+        let speed = config()
+            .get("gamepad_mouse_speed")
+            .and_then(|speed| speed.parse().ok())
+            .unwrap_or(DEFAULT_MOUSE_SPEED);
+        Self::new(&config().table("gamepad_keys"), speed)
+    }
+}
+
+impl Emulation {
+    pub fn new(entries: &[(String, String)], speed: f64) -> Self {
+        let mut binds = vec![];
+        let mut mouse = vec![];
+        for (from, to) in entries {
+            match parse(from, to) {
+                Some(Bind::Input(trigger, input)) => binds.push((trigger, input)),
+                Some(Bind::Mouse(axis, direction)) => mouse.push((axis, direction)),
+                None => println!("gamepad_keys: ignoring {} = {}", from, to),
+            }
+        }
+        Self {
+            binds,
+            mouse,
+            held: HashSet::new(),
+            speed,
+            velocity: [0.0; 2],
+            partial: [0.0; 2],
+            last_frame: Instant::now(),
+            pending: vec![],
+        }
+    }
+
+    /// Looks at a joystick event, whether or not the game is going to get it.
+    pub fn handle(&mut self, event: &SDL_Event) {
+        if unsafe { event.type_ } == SDL_EventType::SDL_JOYAXISMOTION as u8 {
+            let jaxis = unsafe { event.jaxis };
+            for &(axis, direction) in &self.mouse {
+                if axis == jaxis.axis {
+                    self.velocity[direction] = jaxis.value as f64 / 32767.0;
+                }
+            }
+        }
+
+        for (i, &(trigger, input)) in self.binds.iter().enumerate() {
+            let Some((which, active)) = active(trigger, event) else {
+                continue;
+            };
+            let changed = if active {
+                self.held.insert((which, i))
+            } else {
+                self.held.remove(&(which, i))
+            };
+            if changed {
+                self.pending.push(Emulated::Input(input, active));
+            }
+        }
+    }
+
+    /// Whether an axis is pushing the mouse, which needs polling to keep it going.
+    pub fn moving(&self) -> bool {
+        self.velocity != [0.0; 2]
+    }
+
+    /// Everything since the last time, including the mouse moving.
+    pub fn take(&mut self) -> Vec<Emulated> {
+        let now = Instant::now();
+        // Don't jump across the screen after the game stopped polling for a while.
+        let elapsed = (now - self.last_frame).as_secs_f64().min(0.1);
+        self.last_frame = now;
+
+        let mut motion = [0; 2];
+        for ((motion, partial), velocity) in
+            motion.iter_mut().zip(&mut self.partial).zip(self.velocity)
+        {
+            *partial += velocity * self.speed * elapsed;
+            *motion = partial.trunc() as i32;
+            *partial -= *motion as f64;
+        }
+        let mut pending = std::mem::take(&mut self.pending);
+        if motion != [0, 0] {
+            pending.push(Emulated::Motion(motion[0], motion[1]));
+        }
+        pending
+    }
+}
+
+/// Which pad an event is from, and whether it has `trigger` held, if it's about that at all.
+fn active(trigger: Trigger, event: &SDL_Event) -> Option<(u8, bool)> {
+    let type_ = unsafe { event.type_ };
+    let (jaxis, jbutton, jhat) = unsafe { (event.jaxis, event.jbutton, event.jhat) };
+    match trigger {
+        Trigger::Button(button)
+            if (type_ == SDL_EventType::SDL_JOYBUTTONDOWN as u8
+                || type_ == SDL_EventType::SDL_JOYBUTTONUP as u8)
+                && jbutton.button == button =>
+        {
+            Some((jbutton.which, jbutton.state != 0))
+        }
+        Trigger::Axis(axis, positive)
+            if type_ == SDL_EventType::SDL_JOYAXISMOTION as u8 && jaxis.axis == axis =>
+        {
+            let held = if positive {
+                jaxis.value > 16384
+            } else {
+                jaxis.value < -16384
+            };
+            Some((jaxis.which, held))
+        }
+        Trigger::Hat(hat, direction)
+            if type_ == SDL_EventType::SDL_JOYHATMOTION as u8 && jhat.hat == hat =>
+        {
+            Some((jhat.which, jhat.value & direction != 0))
+        }
+        _ => None,
+    }
+}
+
+fn parse(from: &str, to: &str) -> Option<Bind> {
+    let from = from.trim().to_lowercase();
+    let to = to.trim().to_lowercase();
+    if let Some(axis) = from.strip_prefix("axis")
+        && let Some(direction) = ["mousex", "mousey"].iter().position(|&name| name == to)
+    {
+        return Some(Bind::Mouse(axis.parse().ok()?, direction));
+    }
+    let trigger = if let Some(button) = from.strip_prefix("button") {
+        Trigger::Button(button.parse().ok()?)
+    } else if let Some(axis) = from.strip_prefix("axis") {
+        match axis.split_at_checked(axis.len().checked_sub(1)?)? {
+            (axis, "+") => Trigger::Axis(axis.parse().ok()?, true),
+            (axis, "-") => Trigger::Axis(axis.parse().ok()?, false),
+            _ => return None,
+        }
+    } else if let Some(hat) = from.strip_prefix("hat") {
+        let split = hat.find(|c: char| !c.is_ascii_digit())?;
+        let direction = match &hat[split..] {
+            "up" => SDL_HAT_UP,
+            "down" => SDL_HAT_DOWN,
+            "left" => SDL_HAT_LEFT,
+            "right" => SDL_HAT_RIGHT,
+            _ => return None,
+        };
+        Trigger::Hat(hat[..split].parse().ok()?, direction)
+    } else {
+        return None;
+    };
+    Some(Bind::Input(trigger, Input::parse(&to)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::events,
+        type_defs::{SDLKey_SDLK_LEFT, SDLKey_SDLK_RETURN, SDLKey_SDLK_UP},
+    };
+
+    fn emulation() -> Emulation {
+        let entries: Vec<(String, String)> = [
+            ("button0", "return"),
+            ("axis0-", "left"),
+            ("hat0up", "up"),
+            ("button1", "mouse1"),
+            ("axis2", "mousex"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        Emulation::new(&entries, 1000.0)
+    }
+
+    #[test]
+    fn keys() {
+        let mut emulation = emulation();
+        emulation.handle(&events::joy_button_event(0, 0, true));
+        emulation.handle(&events::joy_axis_event(0, 0, -20000));
+        emulation.handle(&events::joy_axis_event(0, 0, -30000));
+        emulation.handle(&events::joy_hat_event(1, 0, SDL_HAT_UP | SDL_HAT_LEFT));
+        emulation.handle(&events::joy_button_event(0, 0, false));
+        emulation.handle(&events::joy_button_event(0, 1, true));
+        assert_eq!(
+            emulation.take(),
+            [
+                Emulated::Input(Input::Key(SDLKey_SDLK_RETURN), true),
+                Emulated::Input(Input::Key(SDLKey_SDLK_LEFT), true),
+                Emulated::Input(Input::Key(SDLKey_SDLK_UP), true),
+                Emulated::Input(Input::Key(SDLKey_SDLK_RETURN), false),
+                Emulated::Input(Input::Button(1), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn mouse() {
+        let mut emulation = emulation();
+        emulation.handle(&events::joy_axis_event(0, 2, 32767));
+        emulation.last_frame = Instant::now() - std::time::Duration::from_millis(50);
+        match emulation.take()[..] {
+            [Emulated::Motion(x, 0)] => assert!((49..=51).contains(&x)),
+            ref other => panic!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use std::{
+    ffi::CString,
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    mem::{size_of, zeroed},
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::fs::OpenOptionsExt,
+    },
+    path::Path,
+    slice,
+};
+
+use libc::{O_CLOEXEC, O_NONBLOCK, input_absinfo, input_event, input_id};
+
+use crate::{
+    joystick::{Source, evemu::Replay},
+    type_defs::{SDL_HAT_CENTERED, SDL_HAT_DOWN, SDL_HAT_LEFT, SDL_HAT_RIGHT, SDL_HAT_UP},
+};
+
+// The bits of linux/input.h and input-event-codes.h we need.
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0;
+const SYN_DROPPED: u16 = 3;
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+pub const ABS_Z: u16 = 0x02;
+pub const ABS_RZ: u16 = 0x05;
+pub const ABS_GAS: u16 = 0x09;
+pub const ABS_BRAKE: u16 = 0x0a;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT3Y: u16 = 0x17;
+pub const ABS_MISC: u16 = 0x28;
+
+const REL_MAX: u16 = 0x0f;
+
+const BTN_MISC: u16 = 0x100;
+const BTN_1: u16 = 0x101;
+const BTN_JOYSTICK: u16 = 0x120;
+const BTN_TRIGGER: u16 = 0x120;
+const BTN_A: u16 = 0x130;
+pub const KEY_MAX: u16 = 0x2ff;
+
+// The ioctls we use, by their number.
+pub const EVIOCGID: u32 = 0x02;
+pub const EVIOCGNAME: u32 = 0x06;
+pub const EVIOCGKEY: u32 = 0x18;
+pub const EVIOCGBIT: u32 = 0x20;
+pub const EVIOCGABS: u32 = 0x40;
+
+// _IOC(_IOC_READ, 'E', nr, size) from asm-generic/ioctl.h.
+fn eviocg(nr: u32, size: usize) -> libc::Ioctl {
+    libc::Ioctl::from((2 << 30) | ((size as u32) << 16) | ((b'E' as u32) << 8) | nr)
+}
+
+// Back out of eviocg. Ioctl isn't u32 everywhere.
+#[allow(clippy::unnecessary_cast)]
+fn ioctl_nr(request: libc::Ioctl) -> (u32, usize) {
+    let request = request as u32;
+    (request & 0xff, ((request >> 16) & 0x3fff) as usize)
+}
+
+enum Events {
+    File(File),
+    // An evemu recording, standing in for the kernel.
+    Replay(Replay),
+}
+
+/// A change in a device's state, with axes already scaled to SDL's range.
+#[derive(PartialEq, Debug)]
+pub enum Change {
+    Axis(usize, i16),
+    Button(usize, bool),
+    Hat(usize, u8),
+    // How far a ball moved, not where it is.
+    Ball(usize, i32, i32),
+}
+
+struct Axis {
+    code: u16,
+    info: input_absinfo,
+}
+
+/// A joystick or gamepad under `/dev/input/event*`.
+pub struct Device {
+    events: Events,
+    name: CString,
+    // SDL's joystick GUID, which is what gamecontrollerdb.txt goes by.
+    guid: String,
+    axes: Vec<Axis>,
+    // Key codes, in the order SDL numbers the buttons.
+    buttons: Vec<u16>,
+    // ABS_HAT*X for each hat, and where it and its Y axis are.
+    hats: Vec<(u16, i32, i32)>,
+    // REL_X, REL_RX etc. for each ball, with the Y axis right after.
+    balls: Vec<u16>,
+    // Set after the kernel dropped events on us, until the next SYN_REPORT.
+    dropped: bool,
+}
+
+impl Device {
+    /// Opens `path` if it's a joystick. Anything else, like keyboards and mice, is `Ok(None)`.
+    pub fn open(path: &Path) -> io::Result<Option<Self>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(O_NONBLOCK | O_CLOEXEC)
+            .open(path)?;
+        Self::probe(Events::File(file))
+    }
+
+    /// A recording from evemu-record, played back in real time from now on.
+    pub fn replay(replay: Replay) -> io::Result<Option<Self>> {
+        Self::probe(Events::Replay(replay))
+    }
+
+    fn probe(events: Events) -> io::Result<Option<Self>> {
+        let mut device = Self {
+            events,
+            name: CString::default(),
+            guid: String::new(),
+            axes: vec![],
+            buttons: vec![],
+            hats: vec![],
+            balls: vec![],
+            dropped: false,
+        };
+        let ev_bits = device.bits(0, EV_ABS as usize + 1)?;
+        let key_bits = device.bits(EV_KEY as u32, KEY_MAX as usize + 1)?;
+        let abs_bits = device.bits(EV_ABS as u32, ABS_MISC as usize + 1)?;
+        let rel_bits = device.bits(EV_REL as u32, REL_MAX as usize + 1)?;
+
+        // Same test as SDL: two axes and something that looks like a joystick or gamepad button.
+        let is_joystick = test_bit(&ev_bits, EV_ABS)
+            && test_bit(&abs_bits, ABS_X)
+            && test_bit(&abs_bits, ABS_Y)
+            && [BTN_TRIGGER, BTN_A, BTN_1]
+                .iter()
+                .any(|&button| test_bit(&key_bits, button));
+        if !is_joystick {
+            return Ok(None);
+        }
+
+        device.name = device.read_name()?;
+        device.guid = guid(&device.read_id()?);
+        // Joystick buttons first, then the misc ones, like SDL.
+        device.buttons = (BTN_JOYSTICK..=KEY_MAX)
+            .chain(BTN_MISC..BTN_JOYSTICK)
+            .filter(|&code| test_bit(&key_bits, code))
+            .collect();
+        for code in ABS_X..=ABS_MISC {
+            // The hats get their own API.
+            if (ABS_HAT0X..=ABS_HAT3Y).contains(&code) || !test_bit(&abs_bits, code) {
+                continue;
+            }
+            let info = device.abs_info(code)?;
+            device.axes.push(Axis { code, info });
+        }
+        // Hats and balls count if either of their axes is there.
+        device.hats = (ABS_HAT0X..=ABS_HAT3Y)
+            .step_by(2)
+            .filter(|&x| test_bit(&abs_bits, x) || test_bit(&abs_bits, x + 1))
+            .map(|x| (x, 0, 0))
+            .collect();
+        device.balls = (0..REL_MAX)
+            .step_by(2)
+            .filter(|&x| test_bit(&rel_bits, x) || test_bit(&rel_bits, x + 1))
+            .collect();
+        Ok(Some(device))
+    }
+
+    fn handle(&mut self, event: &input_event, changes: &mut Vec<Change>) -> io::Result<()> {
+        match (event.type_, event.code) {
+            (EV_SYN, SYN_DROPPED) => self.dropped = true,
+            // Whatever we missed, asking for the whole state catches us up.
+            (EV_SYN, SYN_REPORT) if self.dropped => {
+                self.dropped = false;
+                changes.extend(self.state()?);
+            }
+            _ if self.dropped => {}
+            (EV_ABS, code) if (ABS_HAT0X..=ABS_HAT3Y).contains(&code) => {
+                let x = code & !1;
+                if let Some(i) = self.hats.iter().position(|hat| hat.0 == x) {
+                    let hat = &mut self.hats[i];
+                    if code == x {
+                        hat.1 = event.value;
+                    } else {
+                        hat.2 = event.value;
+                    }
+                    changes.push(Change::Hat(i, hat_value(hat.1, hat.2)));
+                }
+            }
+            (EV_ABS, code) => {
+                if let Some(i) = self.axes.iter().position(|axis| axis.code == code) {
+                    changes.push(Change::Axis(i, normalize(&self.axes[i].info, event.value)));
+                }
+            }
+            (EV_REL, code) => {
+                if let Some(i) = self.balls.iter().position(|&x| x == code & !1) {
+                    if code % 2 == 0 {
+                        changes.push(Change::Ball(i, event.value, 0));
+                    } else {
+                        changes.push(Change::Ball(i, 0, event.value));
+                    }
+                }
+            }
+            (EV_KEY, code) => {
+                if let Some(i) = self.buttons.iter().position(|&button| button == code) {
+                    // 2 is the kernel's autorepeat, which is still just held.
+                    changes.push(Change::Button(i, event.value != 0));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn bits(&self, ev: u32, count: usize) -> io::Result<Vec<u8>> {
+        let len = count.div_ceil(8);
+        self.ioctl_bits(EVIOCGBIT + ev, len)
+    }
+
+    fn ioctl_bits(&self, nr: u32, len: usize) -> io::Result<Vec<u8>> {
+        let mut bits = vec![0u8; len];
+        self.ioctl(eviocg(nr, len), bits.as_mut_ptr())?;
+        Ok(bits)
+    }
+
+    fn abs_info(&self, code: u16) -> io::Result<input_absinfo> {
+        let mut info: input_absinfo = unsafe { zeroed() };
+        self.ioctl(
+            eviocg(EVIOCGABS + code as u32, size_of::<input_absinfo>()),
+            &mut info as *mut input_absinfo as *mut u8,
+        )?;
+        Ok(info)
+    }
+
+    fn read_name(&self) -> io::Result<CString> {
+        let mut name = [0u8; 128];
+        self.ioctl(eviocg(EVIOCGNAME, name.len()), name.as_mut_ptr())?;
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len() - 1);
+        Ok(CString::new(&name[..len]).unwrap_or_default())
+    }
+
+    fn read_id(&self) -> io::Result<input_id> {
+        let mut id: input_id = unsafe { zeroed() };
+        self.ioctl(
+            eviocg(EVIOCGID, size_of::<input_id>()),
+            &mut id as *mut input_id as *mut u8,
+        )?;
+        Ok(id)
+    }
+
+    fn ioctl(&self, request: libc::Ioctl, data: *mut u8) -> io::Result<()> {
+        match &self.events {
+            Events::File(file) => {
+                if unsafe { libc::ioctl(file.as_raw_fd(), request, data) } < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Events::Replay(replay) => {
+                let (nr, size) = ioctl_nr(request);
+                replay.ioctl(nr, unsafe { slice::from_raw_parts_mut(data, size) })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Source for Device {
+    fn name(&self) -> &CString {
+        &self.name
+    }
+
+    fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    fn num_axes(&self) -> usize {
+        self.axes.len()
+    }
+
+    fn num_buttons(&self) -> usize {
+        self.buttons.len()
+    }
+
+    fn num_hats(&self) -> usize {
+        self.hats.len()
+    }
+
+    fn num_balls(&self) -> usize {
+        self.balls.len()
+    }
+
+    fn fd(&self) -> Option<RawFd> {
+        match &self.events {
+            Events::File(file) => Some(file.as_raw_fd()),
+            Events::Replay(_) => None,
+        }
+    }
+
+    /// Where everything is right now, straight from the kernel rather than the event stream.
+    fn state(&mut self) -> io::Result<Vec<Change>> {
+        let mut changes = vec![];
+        for i in 0..self.axes.len() {
+            let info = self.abs_info(self.axes[i].code)?;
+            changes.push(Change::Axis(i, normalize(&info, info.value)));
+        }
+        let keys = self.ioctl_bits(EVIOCGKEY, (KEY_MAX as usize + 1).div_ceil(8))?;
+        for (i, &code) in self.buttons.iter().enumerate() {
+            changes.push(Change::Button(i, test_bit(&keys, code)));
+        }
+        for i in 0..self.hats.len() {
+            let x = self.abs_info(self.hats[i].0)?.value;
+            let y = self.abs_info(self.hats[i].0 + 1)?.value;
+            self.hats[i] = (self.hats[i].0, x, y);
+            changes.push(Change::Hat(i, hat_value(x, y)));
+        }
+        Ok(changes)
+    }
+
+    /// Everything that happened since the last call. Doesn't block.
+    fn read(&mut self) -> io::Result<Vec<Change>> {
+        let mut changes = vec![];
+        let mut events: [input_event; 32] = unsafe { zeroed() };
+        loop {
+            let bytes = unsafe {
+                slice::from_raw_parts_mut(
+                    events.as_mut_ptr() as *mut u8,
+                    size_of::<[input_event; 32]>(),
+                )
+            };
+            let file = match &mut self.events {
+                Events::File(file) => file,
+                Events::Replay(replay) => {
+                    for event in replay.due() {
+                        self.handle(&event, &mut changes)?;
+                    }
+                    return Ok(changes);
+                }
+            };
+            let read = match file.read(bytes) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(changes),
+                Err(err) => return Err(err),
+            };
+            for event in &events[..read / size_of::<input_event>()] {
+                self.handle(event, &mut changes)?;
+            }
+        }
+    }
+}
+
+// Bus, vendor, product and version as little endian 16-bit words, each followed by a zero word.
+// Newer SDLs put a CRC of the name in the word after the bus, but mappings don't rely on it.
+pub fn guid(id: &input_id) -> String {
+    [id.bustype, id.vendor, id.product, id.version]
+        .iter()
+        .map(|word| format!("{:04x}0000", word.swap_bytes()))
+        .collect()
+}
+
+fn test_bit(bits: &[u8], bit: u16) -> bool {
+    bits.get(bit as usize / 8)
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+// Hats are two axes going from -1 to 1, with up and left negative.
+fn hat_value(x: i32, y: i32) -> u8 {
+    let mut value = SDL_HAT_CENTERED;
+    if x < 0 {
+        value |= SDL_HAT_LEFT;
+    } else if x > 0 {
+        value |= SDL_HAT_RIGHT;
+    }
+    if y < 0 {
+        value |= SDL_HAT_UP;
+    } else if y > 0 {
+        value |= SDL_HAT_DOWN;
+    }
+    value
+}
+
+/// Scales an axis from whatever range the device reports to -32767..=32767, with anything
+/// inside its flat zone at 0.
+fn normalize(info: &input_absinfo, value: i32) -> i16 {
+    let (min, max) = (info.minimum as i64, info.maximum as i64);
+    if max <= min {
+        return 0;
+    }
+    // Twice the distance from the center, so odd ranges like 0..=255 don't round.
+    let offset = 2 * value as i64 - (min + max);
+    if offset.abs() <= 2 * info.flat as i64 {
+        return 0;
+    }
+    (offset * 32767 / (max - min)).clamp(-32767, 32767) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(minimum: i32, maximum: i32, flat: i32) -> input_absinfo {
+        input_absinfo {
+            value: 0,
+            minimum,
+            maximum,
+            fuzz: 0,
+            flat,
+            resolution: 0,
+        }
+    }
+
+    #[test]
+    fn normalized() {
+        let stick = info(-32768, 32767, 128);
+        assert_eq!(normalize(&stick, -32768), -32767);
+        assert_eq!(normalize(&stick, 32767), 32767);
+        assert_eq!(normalize(&stick, 100), 0);
+
+        let trigger = info(0, 255, 0);
+        assert_eq!(normalize(&trigger, 0), -32767);
+        assert_eq!(normalize(&trigger, 255), 32767);
+
+        let dualshock = info(0, 255, 15);
+        assert_eq!(normalize(&dualshock, 128), 0);
+        assert_eq!(normalize(&dualshock, 192), 16576);
+    }
+
+    #[test]
+    fn hats() {
+        assert_eq!(hat_value(0, 0), SDL_HAT_CENTERED);
+        assert_eq!(hat_value(-1, 0), SDL_HAT_LEFT);
+        assert_eq!(hat_value(1, -1), SDL_HAT_RIGHT | SDL_HAT_UP);
+        assert_eq!(hat_value(0, 1), SDL_HAT_DOWN);
+    }
+
+    #[test]
+    fn guids() {
+        let id = input_id {
+            bustype: 0x03,
+            vendor: 0x045e,
+            product: 0x028e,
+            version: 0x0110,
+        };
+        assert_eq!(guid(&id), "030000005e0400008e02000010010000");
+    }
+
+    #[test]
+    fn ioctl_numbers() {
+        // EVIOCGNAME(128) and EVIOCGABS(ABS_X) from linux/input.h.
+        assert_eq!(eviocg(0x06, 128) as u32, 0x80804506);
+        assert_eq!(eviocg(0x40, size_of::<input_absinfo>()) as u32, 0x80184540);
+    }
+}
@@ -0,0 +1,253 @@
+// Plays back a recording made with evemu-record, so a real pad's input can be replayed without the
+// pad. It stands in for the kernel underneath `Device`, answering its ioctls from the recording
+// and handing out the events as their time comes.
+
+use std::{
+    collections::VecDeque,
+    ffi::CString,
+    io,
+    mem::{size_of, zeroed},
+    slice,
+    time::Duration,
+};
+
+use libc::{input_absinfo, input_event, input_id};
+
+use crate::joystick::{
+    clock::Clock,
+    evdev::{
+        ABS_BRAKE, ABS_GAS, ABS_MISC, ABS_RZ, ABS_Z, EV_ABS, EV_KEY, EVIOCGABS, EVIOCGBIT,
+        EVIOCGID, EVIOCGKEY, EVIOCGNAME, KEY_MAX,
+    },
+};
+
+// EV_MAX and ABS_MAX.
+const EV_TYPES: usize = 0x20;
+const ABS_CODES: usize = 0x40;
+
+pub struct Replay {
+    name: CString,
+    id: input_id,
+    // What `B:` says the device has, by event type.
+    bits: Vec<Vec<u8>>,
+    // What `A:` says about each axis, with where it is now as the value.
+    abs: Vec<input_absinfo>,
+    // Which keys are down right now.
+    keys: Vec<u8>,
+    events: VecDeque<(Duration, input_event)>,
+    clock: Clock,
+}
+
+impl Replay {
+    pub fn parse(text: &str, clock: Clock) -> Result<Self, String> {
+        let mut replay = Self {
+            name: CString::default(),
+            id: unsafe { zeroed() },
+            bits: vec![vec![]; EV_TYPES],
+            abs: vec![unsafe { zeroed() }; ABS_CODES],
+            keys: vec![0; (KEY_MAX as usize + 1).div_ceil(8)],
+            events: VecDeque::new(),
+            clock,
+        };
+        let mut first_event = None;
+        for (number, line) in text.lines().enumerate() {
+            let error = || format!("line {}: {}", number + 1, line);
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((kind, rest)) = line.split_once(':') else {
+                continue;
+            };
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            match kind {
+                "N" => replay.name = CString::new(rest.trim()).map_err(|_| error())?,
+                "I" => {
+                    let [bus, vendor, product, version] = fields[..] else {
+                        return Err(error());
+                    };
+                    let hex = |field| u16::from_str_radix(field, 16).map_err(|_| error());
+                    replay.id = input_id {
+                        bustype: hex(bus)?,
+                        vendor: hex(vendor)?,
+                        product: hex(product)?,
+                        version: hex(version)?,
+                    };
+                }
+                // The bits for a type can take a few lines, which just carry on from each other.
+                "B" => {
+                    let mut bytes = fields.iter().map(|field| u8::from_str_radix(field, 16));
+                    let ev = bytes.next().ok_or_else(error)?.map_err(|_| error())? as usize;
+                    let bits = replay.bits.get_mut(ev).ok_or_else(error)?;
+                    for byte in bytes {
+                        bits.push(byte.map_err(|_| error())?);
+                    }
+                }
+                "A" => {
+                    let code = fields.first().ok_or_else(error)?;
+                    let code = usize::from_str_radix(code, 16).map_err(|_| error())?;
+                    let numbers: Vec<i32> = fields[1..]
+                        .iter()
+                        .map(|field| field.parse().map_err(|_| error()))
+                        .collect::<Result<_, _>>()?;
+                    let [minimum, maximum, fuzz, flat, resolution, ..] = numbers[..] else {
+                        return Err(error());
+                    };
+                    *replay.abs.get_mut(code).ok_or_else(error)? = input_absinfo {
+                        value: resting(code as u16, minimum, maximum),
+                        minimum,
+                        maximum,
+                        fuzz,
+                        flat,
+                        resolution,
+                    };
+                }
+                "E" => {
+                    let [time, type_, code, value] = fields[..] else {
+                        return Err(error());
+                    };
+                    let time: f64 = time.parse().map_err(|_| error())?;
+                    let start = *first_event.get_or_insert(time);
+                    let mut event: input_event = unsafe { zeroed() };
+                    event.type_ = u16::from_str_radix(type_, 16).map_err(|_| error())?;
+                    event.code = u16::from_str_radix(code, 16).map_err(|_| error())?;
+                    event.value = value.parse().map_err(|_| error())?;
+                    let at = Duration::from_secs_f64((time - start).max(0.0));
+                    replay.events.push_back((at, event));
+                }
+                _ => {}
+            }
+        }
+        Ok(replay)
+    }
+
+    /// The events whose time has come.
+    pub fn due(&mut self) -> Vec<input_event> {
+        let elapsed = self.clock.tick();
+        let mut due = vec![];
+        while let Some(&(at, event)) = self.events.front() {
+            if at > elapsed {
+                break;
+            }
+            self.events.pop_front();
+            match event.type_ {
+                EV_KEY if event.code <= KEY_MAX => {
+                    let (byte, bit) = (event.code as usize / 8, event.code % 8);
+                    if event.value != 0 {
+                        self.keys[byte] |= 1 << bit;
+                    } else {
+                        self.keys[byte] &= !(1 << bit);
+                    }
+                }
+                EV_ABS if (event.code as usize) < ABS_CODES => {
+                    self.abs[event.code as usize].value = event.value;
+                }
+                _ => {}
+            }
+            due.push(event);
+        }
+        due
+    }
+
+    /// Answers one of `Device`'s ioctls like the kernel would.
+    pub fn ioctl(&self, nr: u32, data: &mut [u8]) -> io::Result<()> {
+        match nr {
+            EVIOCGID => copy(data, as_bytes(&self.id)),
+            EVIOCGNAME => copy(data, self.name.as_bytes_with_nul()),
+            EVIOCGKEY => copy(data, &self.keys),
+            _ if nr >= EVIOCGABS && nr - EVIOCGABS < ABS_CODES as u32 => {
+                copy(data, as_bytes(&self.abs[(nr - EVIOCGABS) as usize]))
+            }
+            _ if nr >= EVIOCGBIT && nr - EVIOCGBIT < EV_TYPES as u32 => {
+                copy(data, &self.bits[(nr - EVIOCGBIT) as usize])
+            }
+            _ => return Err(io::ErrorKind::Unsupported.into()),
+        }
+        Ok(())
+    }
+}
+
+// Where an axis is before the recording says otherwise: the middle, except for triggers and
+// pedals, which rest at one end.
+fn resting(code: u16, minimum: i32, maximum: i32) -> i32 {
+    match code {
+        ABS_Z | ABS_RZ | ABS_GAS | ABS_BRAKE => minimum,
+        _ if code <= ABS_MISC => minimum + (maximum - minimum) / 2,
+        _ => 0,
+    }
+}
+
+fn copy(data: &mut [u8], from: &[u8]) {
+    data.fill(0);
+    let len = data.len().min(from.len());
+    data[..len].copy_from_slice(&from[..len]);
+}
+
+fn as_bytes<T>(value: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        joystick::{Change, Source, evdev::Device},
+        type_defs::SDL_HAT_LEFT,
+    };
+
+    // An Xbox 360 pad with only the A button, pressing it and the d-pad's left.
+    fn recording() -> String {
+        let mut keys = [0u8; 96];
+        keys[0x130 / 8] |= 1 << (0x130 % 8);
+        let keys: String = keys
+            .chunks(8)
+            .map(|chunk| {
+                let bytes: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("B: 01 {}\n", bytes.join(" "))
+            })
+            .collect();
+        format!(
+            "# EVEMU 1.3
+N: Microsoft X-Box 360 pad
+I: 0003 045e 028e 0114
+B: 00 0b 00 00 00 00 00 00 00
+{}B: 03 3f 00 03 00 00 00 00 00
+A: 00 -32768 32767 16 128 0
+A: 01 -32768 32767 16 128 0
+A: 02 0 255 0 0 0
+A: 03 -32768 32767 16 128 0
+A: 04 -32768 32767 16 128 0
+A: 05 0 255 0 0 0
+A: 10 -1 1 0 0 0
+A: 11 -1 1 0 0 0
+E: 5.000100 0001 0130 0001	# EV_KEY / BTN_A 1
+E: 5.000100 0003 0010 -0001	# EV_ABS / ABS_HAT0X -1
+E: 5.000100 0000 0000 0000	# ------------ SYN_REPORT (0) ----------
+E: 5.250100 0001 0130 0000	# EV_KEY / BTN_A 0
+",
+            keys
+        )
+    }
+
+    #[test]
+    fn replay() {
+        let replay =
+            Replay::parse(&recording(), Clock::stepped(Duration::from_millis(100))).unwrap();
+        let mut device = Device::replay(replay).unwrap().unwrap();
+        assert_eq!(device.name().to_str(), Ok("Microsoft X-Box 360 pad"));
+        assert_eq!(device.guid(), "030000005e0400008e02000014010000");
+        assert_eq!(
+            (device.num_axes(), device.num_buttons(), device.num_hats()),
+            (6, 1, 1)
+        );
+
+        let state = device.state().unwrap();
+        assert!(state.contains(&Change::Axis(2, -32767)));
+        assert!(state.contains(&Change::Button(0, false)));
+        // The first events are right away, the last one's on the fourth read, 300ms in.
+        assert_eq!(
+            device.read().unwrap(),
+            [Change::Button(0, true), Change::Hat(0, SDL_HAT_LEFT)]
+        );
+        assert_eq!(device.read().unwrap(), []);
+        assert_eq!(device.read().unwrap(), []);
+        assert_eq!(device.read().unwrap(), [Change::Button(0, false)]);
+    }
+}
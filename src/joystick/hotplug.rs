@@ -0,0 +1,135 @@
+// Watches /dev/input with inotify so pads plugged in after the game started still show up.
+//
+// udev creates the node before it's done setting its permissions, so a new node usually can't be
+// opened until the IN_ATTRIB that follows it.
+
+use std::{
+    ffi::CString,
+    fs::File,
+    io::{self, Read},
+    mem::size_of,
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    path::{Path, PathBuf},
+};
+
+use libc::{IN_ATTRIB, IN_CLOEXEC, IN_CREATE, IN_DELETE, IN_NONBLOCK, inotify_event};
+
+#[derive(PartialEq, Debug)]
+pub enum Hotplug {
+    /// Might be a joystick now, so it's worth another try.
+    Added(PathBuf),
+    Removed(PathBuf),
+}
+
+pub struct Watcher {
+    file: File,
+}
+
+impl Watcher {
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = unsafe { File::from_raw_fd(fd) };
+        let dir = CString::new("/dev/input").unwrap();
+        if unsafe { libc::inotify_add_watch(fd, dir.as_ptr(), IN_CREATE | IN_ATTRIB | IN_DELETE) }
+            < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { file })
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+
+    /// Whatever happened since the last time, without blocking.
+    pub fn read(&mut self) -> Vec<Hotplug> {
+        let mut changes = vec![];
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => changes.extend(parse(&buf[..len])),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    println!("couldn't watch /dev/input: {}", err);
+                    break;
+                }
+            }
+        }
+        changes
+    }
+}
+
+fn parse(mut buf: &[u8]) -> Vec<Hotplug> {
+    let mut changes = vec![];
+    while buf.len() >= size_of::<inotify_event>() {
+        let event = unsafe { (buf.as_ptr() as *const inotify_event).read_unaligned() };
+        let end = size_of::<inotify_event>() + event.len as usize;
+        let Some(name) = buf.get(size_of::<inotify_event>()..end) else {
+            break;
+        };
+        buf = &buf[end..];
+
+        // The name is padded with zeros.
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        let Ok(name) = std::str::from_utf8(&name[..len]) else {
+            continue;
+        };
+        if !name.starts_with("event") {
+            continue;
+        }
+        let path = Path::new("/dev/input").join(name);
+        if event.mask & IN_DELETE != 0 {
+            changes.push(Hotplug::Removed(path));
+        } else if event.mask & (IN_CREATE | IN_ATTRIB) != 0 {
+            changes.push(Hotplug::Added(path));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(mask: u32, name: &str) -> Vec<u8> {
+        let mut padded = name.as_bytes().to_vec();
+        padded.resize(16, 0);
+        let event = inotify_event {
+            wd: 1,
+            mask,
+            cookie: 0,
+            len: padded.len() as u32,
+        };
+        let mut buf = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const inotify_event as *const u8,
+                size_of::<inotify_event>(),
+            )
+        }
+        .to_vec();
+        buf.extend(padded);
+        buf
+    }
+
+    #[test]
+    fn events() {
+        let buf = [
+            event(IN_CREATE, "event12"),
+            event(IN_ATTRIB, "js0"),
+            event(IN_DELETE, "event3"),
+        ]
+        .concat();
+        assert_eq!(
+            parse(&buf),
+            [
+                Hotplug::Added("/dev/input/event12".into()),
+                Hotplug::Removed("/dev/input/event3".into())
+            ]
+        );
+    }
+}
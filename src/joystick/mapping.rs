@@ -0,0 +1,339 @@
+// Gamepad mappings in SDL_GameControllerDB's format. Every pad evdev numbers differently, so with a
+// mapping we show the game the same layout no matter what's plugged in, in SDL2's order:
+//
+// axes: leftx, lefty, rightx, righty, lefttrigger, righttrigger
+// buttons: a, b, x, y, back, guide, start, leftstick, rightstick, leftshoulder, rightshoulder
+// hat 0: the d-pad
+//
+// Triggers go from 0 to 32767 like in SDL2, the sticks from -32767 to 32767.
+
+use std::{env, fs};
+
+use crate::{
+    config::{config, config_dir},
+    joystick::State,
+    type_defs::{SDL_HAT_CENTERED, SDL_HAT_DOWN, SDL_HAT_LEFT, SDL_HAT_RIGHT, SDL_HAT_UP},
+};
+
+const BUNDLED: &str = include_str!("gamecontrollerdb.txt");
+
+const AXES: [&str; 6] = [
+    "leftx",
+    "lefty",
+    "rightx",
+    "righty",
+    "lefttrigger",
+    "righttrigger",
+];
+const BUTTONS: [&str; 11] = [
+    "a",
+    "b",
+    "x",
+    "y",
+    "back",
+    "guide",
+    "start",
+    "leftstick",
+    "rightstick",
+    "leftshoulder",
+    "rightshoulder",
+];
+const FIRST_TRIGGER: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Half {
+    Full,
+    Positive,
+    Negative,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Source {
+    Button(usize),
+    Axis(usize, Half, bool),
+    Hat(usize, u8),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Target {
+    Button(usize),
+    Axis(usize, Half),
+    Dpad(u8),
+}
+
+#[derive(Clone)]
+pub struct Mapping {
+    binds: Vec<(Source, Target)>,
+}
+
+impl Mapping {
+    pub fn num_axes(&self) -> usize {
+        AXES.len()
+    }
+
+    /// What the game sees, given what the device says.
+    pub fn map(&self, raw: &State) -> State {
+        let mut state = State {
+            axes: vec![0; AXES.len()],
+            buttons: vec![0; BUTTONS.len()],
+            hats: vec![SDL_HAT_CENTERED],
+        };
+        for (source, target) in &self.binds {
+            let Some((value, full)) = source.value(raw) else {
+                continue;
+            };
+            // How far along it is from 0 to 32767, for anything that only goes one way.
+            let amount = if full { (value + 32767) / 2 } else { value };
+            let pressed = amount > 16383;
+            match *target {
+                Target::Button(button) => state.buttons[button] |= pressed as u8,
+                Target::Dpad(direction) if pressed => state.hats[0] |= direction,
+                Target::Dpad(_) => {}
+                Target::Axis(axis, Half::Full) => {
+                    let value = if full && axis < FIRST_TRIGGER {
+                        value
+                    } else {
+                        amount
+                    };
+                    state.axes[axis] = value.clamp(-32767, 32767) as i16;
+                }
+                // Two buttons making up one axis mustn't cancel each other out while idle.
+                Target::Axis(axis, half) if amount > 0 => {
+                    let value = if half == Half::Negative {
+                        -amount
+                    } else {
+                        amount
+                    };
+                    state.axes[axis] = value.clamp(-32767, 32767) as i16;
+                }
+                Target::Axis(..) => {}
+            }
+        }
+        state
+    }
+
+    fn parse(line: &str) -> Option<(String, Mapping)> {
+        let mut fields = line.trim().split(',');
+        let guid = fields.next()?.trim().to_lowercase();
+        if guid.len() != 32 || fields.next().is_none() {
+            return None;
+        }
+        let mut binds = vec![];
+        for field in fields.filter(|field| !field.is_empty()) {
+            let (target, source) = field.split_once(':')?;
+            if target == "platform" {
+                if source != "Linux" {
+                    return None;
+                }
+                continue;
+            }
+            // Anything SDL 1.2 has nothing for, like paddles or the touchpad.
+            if let (Some(target), Some(source)) = (Target::parse(target), Source::parse(source)) {
+                binds.push((source, target));
+            }
+        }
+        Some((guid, Mapping { binds }))
+    }
+}
+
+impl Source {
+    fn parse(source: &str) -> Option<Self> {
+        let (half, source) = split_half(source);
+        let (source, invert) = match source.strip_suffix('~') {
+            Some(source) => (source, true),
+            None => (source, false),
+        };
+        let (kind, number) = source.split_at_checked(1)?;
+        match kind {
+            "b" => Some(Source::Button(number.parse().ok()?)),
+            "a" => Some(Source::Axis(number.parse().ok()?, half, invert)),
+            "h" => {
+                let (hat, mask) = number.split_once('.')?;
+                Some(Source::Hat(hat.parse().ok()?, mask.parse().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// The value and whether it's a whole axis from -32767 to 32767, rather than 0 to 32767.
+    fn value(&self, raw: &State) -> Option<(i32, bool)> {
+        let value = match *self {
+            Source::Button(button) => *raw.buttons.get(button)? as i32 * 32767,
+            Source::Hat(hat, mask) => (*raw.hats.get(hat)? & mask != 0) as i32 * 32767,
+            Source::Axis(axis, half, invert) => {
+                let mut value = *raw.axes.get(axis)? as i32;
+                if invert {
+                    value = -value;
+                }
+                match half {
+                    Half::Full => return Some((value, true)),
+                    Half::Positive => value.max(0),
+                    Half::Negative => (-value).max(0),
+                }
+            }
+        };
+        Some((value, false))
+    }
+}
+
+impl Target {
+    fn parse(target: &str) -> Option<Self> {
+        let (half, target) = split_half(target);
+        if let Some(axis) = AXES.iter().position(|&name| name == target) {
+            return Some(Target::Axis(axis, half));
+        }
+        if let Some(button) = BUTTONS.iter().position(|&name| name == target) {
+            return Some(Target::Button(button));
+        }
+        match target {
+            "dpup" => Some(Target::Dpad(SDL_HAT_UP)),
+            "dpright" => Some(Target::Dpad(SDL_HAT_RIGHT)),
+            "dpdown" => Some(Target::Dpad(SDL_HAT_DOWN)),
+            "dpleft" => Some(Target::Dpad(SDL_HAT_LEFT)),
+            _ => None,
+        }
+    }
+}
+
+fn split_half(name: &str) -> (Half, &str) {
+    if let Some(name) = name.strip_prefix('+') {
+        (Half::Positive, name)
+    } else if let Some(name) = name.strip_prefix('-') {
+        (Half::Negative, name)
+    } else {
+        (Half::Full, name)
+    }
+}
+
+/// Every mapping we know, later ones winning: the bundled ones, then
+/// `~/.config/sdl12-shim/gamecontrollerdb.txt` (or the `gamecontrollerdb` setting), then
+/// `SDL_GAMECONTROLLERCONFIG` like SDL2 reads, then the `[gamepads]` and `[<game>.gamepads]`
+/// tables. With `raw_joysticks` there are none and the game gets the pads as evdev numbers them.
+pub struct Mappings {
+    mappings: Vec<(String, Mapping)>,
+}
+
+impl Default for Mappings {
+    fn default() -> Self {
+        let mut mappings = Self::new();
+        if config().get_bool("raw_joysticks") {
+            return mappings;
+        }
+        mappings.add(BUNDLED);
+
+        let path = config()
+            .get("gamecontrollerdb")
+            .map(Into::into)
+            .or_else(|| config_dir().map(|dir| dir.join("gamecontrollerdb.txt")));
+        if let Some(path) = path {
+            match fs::read_to_string(&path) {
+                Ok(contents) => mappings.add(&contents),
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    println!("error reading {}: {}", path.display(), err)
+                }
+                Err(_) => {}
+            }
+        }
+        if let Ok(contents) = env::var("SDL_GAMECONTROLLERCONFIG") {
+            mappings.add(&contents);
+        }
+        for (guid, mapping) in config().table("gamepads") {
+            mappings.add(&format!("{},{}", guid, mapping));
+        }
+        mappings
+    }
+}
+
+impl Mappings {
+    pub fn new() -> Self {
+        Self { mappings: vec![] }
+    }
+
+    pub fn add(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((guid, mapping)) = Mapping::parse(line) {
+                self.mappings.push((normalize(&guid), mapping));
+            }
+        }
+    }
+
+    /// The mapping for a GUID from evdev, or failing that one for another version of the pad.
+    pub fn find(&self, guid: &str) -> Option<&Mapping> {
+        if guid.len() != 32 {
+            return None;
+        }
+        let guid = normalize(guid);
+        let mappings = || self.mappings.iter().rev();
+        mappings()
+            .find(|(other, _)| *other == guid)
+            .or_else(|| mappings().find(|(other, _)| other[..24] == guid[..24]))
+            .map(|(_, mapping)| mapping)
+    }
+}
+
+// Leaves the name CRC out.
+fn normalize(guid: &str) -> String {
+    let guid = guid.to_lowercase();
+    format!("{}0000{}", &guid[..4], &guid[8..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XBOX: &str = "030000005e0400008e02000010010000";
+
+    #[test]
+    fn parse() {
+        let (guid, mapping) = Mapping::parse(BUNDLED.lines().nth(2).unwrap()).unwrap();
+        assert_eq!(guid, XBOX);
+        assert!(
+            mapping
+                .binds
+                .contains(&(Source::Button(0), Target::Button(0)))
+        );
+        assert!(
+            mapping
+                .binds
+                .contains(&(Source::Hat(0, 8), Target::Dpad(SDL_HAT_LEFT)))
+        );
+        assert!(mapping.binds.contains(&(
+            Source::Axis(5, Half::Full, false),
+            Target::Axis(5, Half::Full)
+        )));
+        assert!(Mapping::parse(&format!("{},Pad,a:b0,platform:Windows,", XBOX)).is_none());
+    }
+
+    #[test]
+    fn find() {
+        let mut mappings = Mappings { mappings: vec![] };
+        mappings.add(BUNDLED);
+        assert!(mappings.find(XBOX).is_some());
+        // A CRC or a different firmware version still match.
+        assert!(mappings.find("0300d8a15e0400008e02000010010000").is_some());
+        assert!(mappings.find("030000005e0400008e02000014010000").is_some());
+        assert!(mappings.find("030000005e0400008f02000010010000").is_none());
+    }
+
+    #[test]
+    fn map() {
+        let mut mappings = Mappings { mappings: vec![] };
+        mappings.add(&format!(
+            "{},Pad,a:b1,lefttrigger:a2,lefty:a1~,dpup:b0,+rightx:b2,-rightx:b3,",
+            XBOX
+        ));
+        let raw = State {
+            axes: vec![0, 1000, -32767],
+            buttons: vec![1, 1, 0, 1],
+            hats: vec![],
+        };
+        let state = mappings.find(XBOX).unwrap().map(&raw);
+        assert_eq!(state.axes, [0, -1000, -32767, 0, 0, 0]);
+        assert_eq!(state.buttons, [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(state.hats, [SDL_HAT_UP]);
+    }
+}
@@ -0,0 +1,592 @@
+// SDL's joystick API on top of evdev. Joysticks are numbered like SDL numbers them, in the order of
+// their /dev/input/event* nodes, and the SDL_Joystick pointers we hand out point at our own
+// `Joystick`s. Ones plugged in later go on the end, and unplugging one keeps its spot for when it
+// comes back.
+
+use std::{
+    ffi::{CString, c_char},
+    fs, io,
+    os::fd::RawFd,
+    path::{Path, PathBuf},
+    ptr::{self, null, null_mut},
+    time::Duration,
+};
+
+use crate::{
+    backend::events,
+    config::config,
+    joystick::{
+        clock::Clock,
+        emulation::{Emulated, Emulation},
+        evdev::{Change, Device},
+        evemu::Replay,
+        hotplug::{Hotplug, Watcher},
+        mapping::{Mapping, Mappings},
+        script::Script,
+        tuning::AxisSettings,
+    },
+    type_defs::{SDL_Event, SDL_Joystick},
+};
+
+mod clock;
+pub mod emulation;
+mod evdev;
+mod evemu;
+mod hotplug;
+mod mapping;
+mod script;
+mod tuning;
+
+// How often SDL_WaitEvent looks at joysticks that have nothing to wait on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Where a joystick's input comes from: a real device, or one of the virtual ones for testing.
+trait Source {
+    fn name(&self) -> &CString;
+    fn guid(&self) -> &str;
+    fn num_axes(&self) -> usize;
+    fn num_buttons(&self) -> usize;
+    fn num_hats(&self) -> usize;
+    fn num_balls(&self) -> usize;
+    /// Where everything is right now.
+    fn state(&mut self) -> io::Result<Vec<Change>>;
+    /// Everything that happened since the last call. Doesn't block.
+    fn read(&mut self) -> io::Result<Vec<Change>>;
+    /// What to wait on for more, if anything. Without one we have to keep checking.
+    fn fd(&self) -> Option<RawFd> {
+        None
+    }
+}
+
+/// Where everything on a joystick is.
+#[derive(Clone, Default, PartialEq, Debug)]
+struct State {
+    axes: Vec<i16>,
+    buttons: Vec<u8>,
+    hats: Vec<u8>,
+}
+
+pub struct Joystick {
+    path: PathBuf,
+    // None while it's unplugged, in which case it reads as centered with nothing pressed.
+    device: Option<Box<dyn Source>>,
+    // Kept around so we know it when it comes back.
+    name: CString,
+    guid: String,
+    mapping: Option<Mapping>,
+    tuning: Vec<AxisSettings>,
+    // What the device says, and what the game sees after the mapping.
+    raw: State,
+    state: State,
+    // How far each ball moved since the game last asked.
+    balls: Vec<(i32, i32)>,
+    // How many times the game opened it.
+    opened: u32,
+}
+
+impl Joystick {
+    fn new(
+        path: &Path,
+        device: Box<dyn Source>,
+        mappings: &Mappings,
+        tuning: &[(String, String)],
+    ) -> Self {
+        let mapping = mappings.find(device.guid()).cloned();
+        let axes = mapping
+            .as_ref()
+            .map_or(device.num_axes(), Mapping::num_axes);
+        let name = device.name().to_string_lossy();
+        let tuning = tuning::settings(tuning, &name, device.guid(), axes);
+        let mut joystick = Self {
+            path: path.to_path_buf(),
+            name: device.name().clone(),
+            guid: device.guid().to_string(),
+            mapping,
+            tuning,
+            raw: State::default(),
+            state: State::default(),
+            balls: vec![],
+            opened: 0,
+            device: None,
+        };
+        joystick.connect(path, device, 0);
+        joystick
+    }
+
+    /// Starts reading from `device`, which is either the first one or the same pad plugged
+    /// back in.
+    fn connect(&mut self, path: &Path, device: Box<dyn Source>, which: u8) -> Vec<SDL_Event> {
+        self.path = path.to_path_buf();
+        self.raw = State {
+            axes: vec![0; device.num_axes()],
+            buttons: vec![0; device.num_buttons()],
+            hats: vec![0; device.num_hats()],
+        };
+        // It's unplugged until now, so the game's seeing everything centered.
+        self.state = self.map();
+        self.balls.resize(device.num_balls(), (0, 0));
+        let device = self.device.insert(device);
+        match device.state() {
+            Ok(changes) => self.apply(changes, which),
+            Err(err) => {
+                println!("couldn't read {}: {}", path.display(), err);
+                vec![]
+            }
+        }
+    }
+
+    /// Lets go of everything, for when it's unplugged.
+    fn disconnect(&mut self, which: u8) -> Vec<SDL_Event> {
+        self.device = None;
+        self.raw = State {
+            axes: vec![0; self.raw.axes.len()],
+            buttons: vec![0; self.raw.buttons.len()],
+            hats: vec![0; self.raw.hats.len()],
+        };
+        self.apply(vec![], which)
+    }
+
+    fn map(&self) -> State {
+        let mut state = match &self.mapping {
+            Some(mapping) => mapping.map(&self.raw),
+            None => self.raw.clone(),
+        };
+        for (value, settings) in state.axes.iter_mut().zip(&self.tuning) {
+            *value = settings.apply(*value);
+        }
+        state
+    }
+
+    /// Returns the events for whatever actually changed, as joystick `which`.
+    fn apply(&mut self, changes: Vec<Change>, which: u8) -> Vec<SDL_Event> {
+        // Balls send X and Y separately, but it's one event in SDL.
+        let mut moved = vec![(0, 0); self.balls.len()];
+        for change in changes {
+            match change {
+                Change::Axis(axis, value) => self.raw.axes[axis] = value,
+                Change::Button(button, pressed) => self.raw.buttons[button] = pressed as u8,
+                Change::Hat(hat, value) => self.raw.hats[hat] = value,
+                Change::Ball(ball, dx, dy) => {
+                    moved[ball].0 += dx;
+                    moved[ball].1 += dy;
+                }
+            }
+        }
+        let state = self.map();
+
+        let mut events = vec![];
+        for (axis, (&old, &new)) in self.state.axes.iter().zip(&state.axes).enumerate() {
+            if old != new {
+                events.push(events::joy_axis_event(which, axis as u8, new));
+            }
+        }
+        for (button, (&old, &new)) in self.state.buttons.iter().zip(&state.buttons).enumerate() {
+            if old != new {
+                events.push(events::joy_button_event(which, button as u8, new != 0));
+            }
+        }
+        for (hat, (&old, &new)) in self.state.hats.iter().zip(&state.hats).enumerate() {
+            if old != new {
+                events.push(events::joy_hat_event(which, hat as u8, new));
+            }
+        }
+        for (ball, (dx, dy)) in moved.into_iter().enumerate() {
+            if (dx, dy) != (0, 0) {
+                self.balls[ball].0 += dx;
+                self.balls[ball].1 += dy;
+                events.push(events::joy_ball_event(
+                    which, ball as u8, dx as i16, dy as i16,
+                ));
+            }
+        }
+        self.state = state;
+        events
+    }
+
+    fn update(&mut self, which: u8) -> Vec<SDL_Event> {
+        let Some(device) = &mut self.device else {
+            return vec![];
+        };
+        match device.read() {
+            Ok(changes) => self.apply(changes, which),
+            // Unplugged, we'll usually hear about it from inotify too.
+            Err(err) if err.raw_os_error() == Some(libc::ENODEV) => self.disconnect(which),
+            Err(err) => {
+                println!("couldn't read {}: {}", self.path.display(), err);
+                vec![]
+            }
+        }
+    }
+}
+
+pub struct Joysticks {
+    // Boxed so the pointers we give the game stay put.
+    #[allow(clippy::vec_box)]
+    joysticks: Vec<Box<Joystick>>,
+    mappings: Mappings,
+    // The `[axes]` tables, for tuning.rs.
+    tuning: Vec<(String, String)>,
+    watcher: Option<Watcher>,
+    emulation: Emulation,
+}
+
+impl Default for Joysticks {
+    fn default() -> Self {
+        let mut joysticks = Self::new(
+            Mappings::default(),
+            config().table("axes"),
+            Emulation::default(),
+        );
+        // With virtual ones, those are all there is so that every run goes the same.
+        if let Some(paths) = config().get("virtual_joysticks") {
+            for path in paths.split(':').map(Path::new) {
+                match open_virtual(path) {
+                    Ok(source) => {
+                        joysticks.insert(path, source);
+                    }
+                    Err(err) => println!("couldn't load {}: {}", path.display(), err),
+                }
+            }
+            return joysticks;
+        }
+
+        joysticks.watcher = Watcher::new()
+            .inspect_err(|err| println!("couldn't watch /dev/input for new joysticks: {}", err))
+            .ok();
+        for path in scan() {
+            joysticks.add(&path);
+        }
+        joysticks
+    }
+}
+
+impl Joysticks {
+    fn new(mappings: Mappings, tuning: Vec<(String, String)>, emulation: Emulation) -> Self {
+        Self {
+            joysticks: vec![],
+            mappings,
+            tuning,
+            watcher: None,
+            emulation,
+        }
+    }
+
+    pub fn count(&self) -> i32 {
+        self.joysticks.len() as i32
+    }
+
+    pub fn name(&self, index: i32) -> *const c_char {
+        match self.by_index(index) {
+            Some(joystick) => joystick.name.as_ptr(),
+            None => null(),
+        }
+    }
+
+    pub fn open(&mut self, index: i32) -> *mut SDL_Joystick {
+        let Some(joystick) = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.joysticks.get_mut(index))
+        else {
+            println!("SDL_JoystickOpen: there's no joystick {}", index);
+            return null_mut();
+        };
+        joystick.opened += 1;
+        &mut **joystick as *mut Joystick as *mut SDL_Joystick
+    }
+
+    pub fn opened(&self, index: i32) -> i32 {
+        self.by_index(index).is_some_and(|j| j.opened > 0) as i32
+    }
+
+    pub fn index(&self, joystick: *mut SDL_Joystick) -> i32 {
+        self.get(joystick)
+            .and_then(|j| self.joysticks.iter().position(|k| ptr::eq(&**k, j)))
+            .map_or(-1, |index| index as i32)
+    }
+
+    pub fn close(&mut self, joystick: *mut SDL_Joystick) {
+        if let Ok(index) = usize::try_from(self.index(joystick)) {
+            self.joysticks[index].opened -= 1;
+        }
+    }
+
+    pub fn num_axes(&self, joystick: *mut SDL_Joystick) -> i32 {
+        self.get(joystick).map_or(-1, |j| j.state.axes.len() as i32)
+    }
+
+    pub fn num_buttons(&self, joystick: *mut SDL_Joystick) -> i32 {
+        self.get(joystick)
+            .map_or(-1, |j| j.state.buttons.len() as i32)
+    }
+
+    pub fn num_hats(&self, joystick: *mut SDL_Joystick) -> i32 {
+        self.get(joystick).map_or(-1, |j| j.state.hats.len() as i32)
+    }
+
+    pub fn num_balls(&self, joystick: *mut SDL_Joystick) -> i32 {
+        self.get(joystick).map_or(-1, |j| j.balls.len() as i32)
+    }
+
+    pub fn axis(&self, joystick: *mut SDL_Joystick, axis: i32) -> i16 {
+        self.get(joystick)
+            .and_then(|j| j.state.axes.get(usize::try_from(axis).ok()?).copied())
+            .unwrap_or(0)
+    }
+
+    pub fn button(&self, joystick: *mut SDL_Joystick, button: i32) -> u8 {
+        self.get(joystick)
+            .and_then(|j| j.state.buttons.get(usize::try_from(button).ok()?).copied())
+            .unwrap_or(0)
+    }
+
+    pub fn hat(&self, joystick: *mut SDL_Joystick, hat: i32) -> u8 {
+        self.get(joystick)
+            .and_then(|j| j.state.hats.get(usize::try_from(hat).ok()?).copied())
+            .unwrap_or(0)
+    }
+
+    /// SDL_JoystickGetBall: how far the ball moved since the last call.
+    pub fn ball(
+        &mut self,
+        joystick: *mut SDL_Joystick,
+        ball: i32,
+        dx: *mut i32,
+        dy: *mut i32,
+    ) -> i32 {
+        let Ok(index) = usize::try_from(self.index(joystick)) else {
+            return -1;
+        };
+        let Some(ball) = usize::try_from(ball)
+            .ok()
+            .and_then(|ball| self.joysticks[index].balls.get_mut(ball))
+        else {
+            println!("SDL_JoystickGetBall: there's no ball {}", ball);
+            return -1;
+        };
+        let (x, y) = std::mem::take(ball);
+        unsafe {
+            if !dx.is_null() {
+                *dx = x;
+            }
+            if !dy.is_null() {
+                *dy = y;
+            }
+        }
+        0
+    }
+
+    /// Reads whatever the devices sent since the last time. Returns the events for the
+    /// joysticks the game opened, SDL_JoystickEventState is up to the queue.
+    pub fn update(&mut self) -> Vec<SDL_Event> {
+        let mut events = vec![];
+        let changes = self.watcher.as_mut().map(Watcher::read).unwrap_or_default();
+        for change in changes {
+            match change {
+                Hotplug::Added(path) => {
+                    if let Some((index, changed)) = self.add(&path) {
+                        self.queue(index, changed, &mut events);
+                    }
+                }
+                Hotplug::Removed(path) => {
+                    let Some(index) = self
+                        .joysticks
+                        .iter()
+                        .position(|j| j.path == path && j.device.is_some())
+                    else {
+                        continue;
+                    };
+                    let changed = self.joysticks[index].disconnect(index as u8);
+                    self.queue(index, changed, &mut events);
+                }
+            }
+        }
+        for index in 0..self.joysticks.len() {
+            let changed = self.joysticks[index].update(index as u8);
+            self.queue(index, changed, &mut events);
+        }
+        events
+    }
+
+    /// What SDL_WaitEvent has to wait on for the joysticks.
+    pub fn fds(&self) -> Vec<RawFd> {
+        let devices = self.joysticks.iter().filter_map(|j| j.device.as_ref());
+        let watcher = self.watcher.as_ref().map(Watcher::fd);
+        devices
+            .filter_map(|device| device.fd())
+            .chain(watcher)
+            .collect()
+    }
+
+    /// How long SDL_WaitEvent can go before checking on the joysticks again, if it can't just
+    /// wait on them.
+    pub fn timeout(&self) -> Option<Duration> {
+        let unwatched = self
+            .joysticks
+            .iter()
+            .filter_map(|j| j.device.as_ref())
+            .any(|device| device.fd().is_none());
+        (unwatched || self.emulation.moving()).then_some(POLL_INTERVAL)
+    }
+
+    /// What the pads did as the keyboard and mouse since the last time.
+    pub fn emulated(&mut self) -> Vec<Emulated> {
+        self.emulation.take()
+    }
+
+    fn queue(&mut self, index: usize, changed: Vec<SDL_Event>, events: &mut Vec<SDL_Event>) {
+        for event in &changed {
+            self.emulation.handle(event);
+        }
+        if self.joysticks[index].opened > 0 {
+            events.extend(changed);
+        }
+    }
+
+    /// Opens the node if it's a joystick we aren't reading yet. One we had before gets its old
+    /// index back, anything else goes on the end. Returns the index and the events for the state
+    /// it's in.
+    fn add(&mut self, path: &Path) -> Option<(usize, Vec<SDL_Event>)> {
+        if self
+            .joysticks
+            .iter()
+            .any(|j| j.path == path && j.device.is_some())
+        {
+            return None;
+        }
+        let device = match Device::open(path) {
+            Ok(device) => device?,
+            Err(err) => {
+                // Most of these are just devices we aren't allowed to read, like keyboards.
+                if err.kind() != std::io::ErrorKind::PermissionDenied {
+                    println!("couldn't open {}: {}", path.display(), err);
+                }
+                return None;
+            }
+        };
+        Some(self.insert(path, Box::new(device)))
+    }
+
+    fn insert(&mut self, path: &Path, device: Box<dyn Source>) -> (usize, Vec<SDL_Event>) {
+        let returning = self.joysticks.iter().position(|j| {
+            j.device.is_none() && j.guid == device.guid() && j.name == *device.name()
+        });
+        match returning {
+            Some(index) => {
+                let changed = self.joysticks[index].connect(path, device, index as u8);
+                (index, changed)
+            }
+            None => {
+                let joystick = Joystick::new(path, device, &self.mappings, &self.tuning);
+                self.joysticks.push(Box::new(joystick));
+                (self.joysticks.len() - 1, vec![])
+            }
+        }
+    }
+
+    fn by_index(&self, index: i32) -> Option<&Joystick> {
+        self.joysticks
+            .get(usize::try_from(index).ok()?)
+            .map(|joystick| &**joystick)
+    }
+
+    // Only pointers we handed out from SDL_JoystickOpen count, anything else is the game's bug.
+    fn get(&self, joystick: *mut SDL_Joystick) -> Option<&Joystick> {
+        let found = self
+            .joysticks
+            .iter()
+            .find(|j| ptr::eq(&***j, joystick as *const Joystick))
+            .map(|joystick| &**joystick)
+            .filter(|joystick| joystick.opened > 0);
+        if found.is_none() {
+            println!("invalid joystick {:?}", joystick);
+        }
+        found
+    }
+}
+
+/// A script, or a recording from evemu-record.
+fn open_virtual(path: &Path) -> io::Result<Box<dyn Source>> {
+    let text = fs::read_to_string(path)?;
+    if text.starts_with("# EVEMU") {
+        let replay = Replay::parse(&text, Clock::default()).map_err(io::Error::other)?;
+        match Device::replay(replay)? {
+            Some(device) => Ok(Box::new(device)),
+            None => Err(io::Error::other("the recording isn't of a joystick")),
+        }
+    } else {
+        let script = Script::parse(&text, Clock::default()).map_err(io::Error::other)?;
+        Ok(Box::new(script))
+    }
+}
+
+/// Every /dev/input/event* node, in order of their number.
+fn scan() -> Vec<PathBuf> {
+    let Ok(dir) = fs::read_dir("/dev/input") else {
+        return vec![];
+    };
+    let mut nodes: Vec<(u32, PathBuf)> = dir
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let number = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("event")?
+                .parse()
+                .ok()?;
+            Some((number, path))
+        })
+        .collect();
+    nodes.sort();
+    nodes.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_defs::{SDL_EventType, SDL_HAT_RIGHT};
+
+    const SCRIPT: &str = "name = Scripted
+        axes = 2
+        buttons = 2
+        hats = 1
+        0 axis 1 12000
+        0 button 1 down
+        0 hat 0 right
+        250 button 1 up";
+
+    #[test]
+    fn scripted() {
+        let mut joysticks = Joysticks::new(Mappings::new(), vec![], Emulation::new(&[], 0.0));
+        for path in ["first", "second"] {
+            let script = Script::parse(SCRIPT, Clock::stepped(Duration::from_millis(100))).unwrap();
+            joysticks.insert(Path::new(path), Box::new(script));
+        }
+        assert_eq!(joysticks.count(), 2);
+
+        // Only the opened one sends events.
+        let joystick = joysticks.open(1);
+        let events = joysticks.update();
+        let types: Vec<(u8, u8)> = events
+            .iter()
+            .map(|event| unsafe { (event.type_, event.jaxis.which) })
+            .collect();
+        assert_eq!(
+            types,
+            [
+                (SDL_EventType::SDL_JOYAXISMOTION as u8, 1),
+                (SDL_EventType::SDL_JOYBUTTONDOWN as u8, 1),
+                (SDL_EventType::SDL_JOYHATMOTION as u8, 1),
+            ]
+        );
+        assert_eq!(joysticks.num_axes(joystick), 2);
+        assert_eq!(joysticks.axis(joystick, 1), 12000);
+        assert_eq!(joysticks.button(joystick, 1), 1);
+        assert_eq!(joysticks.hat(joystick, 0), SDL_HAT_RIGHT);
+
+        // Every update is 100ms on, so the release is in the fourth.
+        assert!(joysticks.update().is_empty());
+        assert!(joysticks.update().is_empty());
+        assert_eq!(joysticks.update().len(), 1);
+        assert_eq!(joysticks.button(joystick, 1), 0);
+    }
+}
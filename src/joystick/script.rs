@@ -0,0 +1,197 @@
+// A made up joystick that does what a script says, for testing without a pad. The script says what
+// the joystick has, then what happens when, in milliseconds from when it's loaded (or by its
+// `Clock`):
+//
+// name = Virtual Pad
+// guid = 030000005e0400008e02000010010000
+// axes = 2
+// buttons = 4
+// hats = 1
+// balls = 1
+// 0 axis 0 -32767
+// 100 button 1 down
+// 250 hat 0 up+left
+// 300 ball 0 5 -3
+//
+// Axes take SDL's values, and hats centered, up, down, left and right, or a mix with +.
+
+use std::{collections::VecDeque, ffi::CString, io, time::Duration};
+
+use crate::{
+    joystick::{Change, Source, clock::Clock},
+    type_defs::{SDL_HAT_CENTERED, SDL_HAT_DOWN, SDL_HAT_LEFT, SDL_HAT_RIGHT, SDL_HAT_UP},
+};
+
+#[derive(Default)]
+struct Counts {
+    axes: usize,
+    buttons: usize,
+    hats: usize,
+    balls: usize,
+}
+
+pub struct Script {
+    name: CString,
+    guid: String,
+    counts: Counts,
+    changes: VecDeque<(Duration, Change)>,
+    clock: Clock,
+}
+
+impl Script {
+    pub fn parse(text: &str, clock: Clock) -> Result<Self, String> {
+        let mut script = Self {
+            name: CString::new("Virtual Joystick").unwrap(),
+            guid: String::new(),
+            counts: Counts::default(),
+            changes: VecDeque::new(),
+            clock,
+        };
+        for (number, line) in text.lines().enumerate() {
+            let error = || format!("line {}: {}", number + 1, line);
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim();
+                let count = || value.parse().map_err(|_| error());
+                match key.trim() {
+                    "name" => script.name = CString::new(value).map_err(|_| error())?,
+                    "guid" => script.guid = value.to_lowercase(),
+                    "axes" => script.counts.axes = count()?,
+                    "buttons" => script.counts.buttons = count()?,
+                    "hats" => script.counts.hats = count()?,
+                    "balls" => script.counts.balls = count()?,
+                    _ => return Err(error()),
+                }
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [at, kind, index, values @ ..] = &fields[..] else {
+                return Err(error());
+            };
+            let at = Duration::from_millis(at.parse().map_err(|_| error())?);
+            let index: usize = index.parse().map_err(|_| error())?;
+            let number = |value: &str| value.parse::<i32>().map_err(|_| error());
+            let change = match (*kind, values) {
+                ("axis", [value]) if index < script.counts.axes => {
+                    Change::Axis(index, number(value)?.clamp(-32767, 32767) as i16)
+                }
+                ("button", [state]) if index < script.counts.buttons => {
+                    let pressed = match *state {
+                        "down" | "1" => true,
+                        "up" | "0" => false,
+                        _ => return Err(error()),
+                    };
+                    Change::Button(index, pressed)
+                }
+                ("hat", [value]) if index < script.counts.hats => {
+                    Change::Hat(index, hat_value(value).ok_or_else(error)?)
+                }
+                ("ball", [dx, dy]) if index < script.counts.balls => {
+                    Change::Ball(index, number(dx)?, number(dy)?)
+                }
+                _ => return Err(error()),
+            };
+            script.changes.push_back((at, change));
+        }
+        script.changes.make_contiguous().sort_by_key(|&(at, _)| at);
+        Ok(script)
+    }
+}
+
+impl Source for Script {
+    fn name(&self) -> &CString {
+        &self.name
+    }
+
+    fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    fn num_axes(&self) -> usize {
+        self.counts.axes
+    }
+
+    fn num_buttons(&self) -> usize {
+        self.counts.buttons
+    }
+
+    fn num_hats(&self) -> usize {
+        self.counts.hats
+    }
+
+    fn num_balls(&self) -> usize {
+        self.counts.balls
+    }
+
+    // Everything starts centered with nothing pressed.
+    fn state(&mut self) -> io::Result<Vec<Change>> {
+        Ok(vec![])
+    }
+
+    fn read(&mut self) -> io::Result<Vec<Change>> {
+        let elapsed = self.clock.tick();
+        let mut changes = vec![];
+        while self.changes.front().is_some_and(|&(at, _)| at <= elapsed) {
+            changes.extend(self.changes.pop_front().map(|(_, change)| change));
+        }
+        Ok(changes)
+    }
+}
+
+fn hat_value(value: &str) -> Option<u8> {
+    value
+        .split('+')
+        .try_fold(SDL_HAT_CENTERED, |hat, direction| {
+            let direction = match direction {
+                "centered" => SDL_HAT_CENTERED,
+                "up" => SDL_HAT_UP,
+                "right" => SDL_HAT_RIGHT,
+                "down" => SDL_HAT_DOWN,
+                "left" => SDL_HAT_LEFT,
+                _ => direction.parse().ok()?,
+            };
+            Some(hat | direction)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let mut script = Script::parse(
+            "name = Test Pad
+            axes = 2
+            buttons = 1
+            hats = 1
+            250 button 0 up
+            0 axis 1 -200
+            0 hat 0 up+left
+            0 button 0 down",
+            Clock::stepped(Duration::from_millis(100)),
+        )
+        .unwrap();
+        assert_eq!(script.name().to_str(), Ok("Test Pad"));
+        assert_eq!(
+            script.read().unwrap(),
+            [
+                Change::Axis(1, -200),
+                Change::Hat(0, SDL_HAT_UP | SDL_HAT_LEFT),
+                Change::Button(0, true)
+            ]
+        );
+        // 100 and 200, then 300 is past the release.
+        assert_eq!(script.read().unwrap(), []);
+        assert_eq!(script.read().unwrap(), []);
+        assert_eq!(script.read().unwrap(), [Change::Button(0, false)]);
+
+        assert!(Script::parse("axes = 1\n0 axis 1 5", Clock::stepped(Duration::ZERO)).is_err());
+        assert!(
+            Script::parse("hats = 1\n0 hat 0 sideways", Clock::stepped(Duration::ZERO)).is_err()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// Deadzones, inversion, sensitivity and response curves for axes, from the `[axes]` and
+// `[<game>.axes]` tables. Keys are the setting, optionally after the axis and the pad it's for:
+//
+// deadzone = 0.1                          every axis on every pad
+// 0.curve = 2                             axis 0 on every pad
+// Xbox 360 Controller/0.invert = true     axis 0 on that pad, by name or GUID
+// Xbox 360 Controller/*.sensitivity = 1.5 every axis on that pad
+//
+// The more specific ones win, and otherwise later ones do. Axes are numbered like the game sees
+// them, so after any gamepad mapping.
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AxisSettings {
+    // How much of the middle counts as centered, from 0 to 1.
+    deadzone: f64,
+    // How much of the edge counts as all the way.
+    outer_deadzone: f64,
+    invert: bool,
+    sensitivity: f64,
+    // 1 is linear, higher is gentler around the middle.
+    curve: f64,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.0,
+            outer_deadzone: 0.0,
+            invert: false,
+            sensitivity: 1.0,
+            curve: 1.0,
+        }
+    }
+}
+
+impl AxisSettings {
+    pub fn apply(&self, value: i16) -> i16 {
+        let value = value as f64 / 32767.0;
+        let range = 1.0 - self.deadzone - self.outer_deadzone;
+        let mut amount = if range <= 0.0 {
+            (value.abs() > self.deadzone) as i32 as f64
+        } else {
+            ((value.abs() - self.deadzone) / range).clamp(0.0, 1.0)
+        };
+        amount = (amount.powf(self.curve) * self.sensitivity).min(1.0);
+        if (value < 0.0) != self.invert {
+            amount = -amount;
+        }
+        (amount * 32767.0).round() as i16
+    }
+
+    fn set(&mut self, setting: &str, value: &str) {
+        let parsed = match setting {
+            "invert" => {
+                self.invert = matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on");
+                return;
+            }
+            _ => value.parse::<f64>(),
+        };
+        let Ok(value) = parsed else {
+            println!("axes: {} isn't a number: {}", setting, value);
+            return;
+        };
+        match setting {
+            "deadzone" => self.deadzone = value.clamp(0.0, 1.0),
+            "outer_deadzone" => self.outer_deadzone = value.clamp(0.0, 1.0),
+            "sensitivity" => self.sensitivity = value.max(0.0),
+            "curve" if value > 0.0 => self.curve = value,
+            _ => println!("axes: ignoring {} = {}", setting, value),
+        }
+    }
+}
+
+/// The settings for each of a pad's axes, from `entries` like the config tables have them.
+pub fn settings(
+    entries: &[(String, String)],
+    name: &str,
+    guid: &str,
+    axes: usize,
+) -> Vec<AxisSettings> {
+    let mut matching: Vec<(u8, Option<usize>, &str, &str)> = vec![];
+    for (key, value) in entries {
+        let (scope, setting) = key.rsplit_once('.').unwrap_or(("*", key));
+        let (device, axis) = match scope.rsplit_once('/') {
+            Some((device, axis)) => (Some(device.trim()), axis.trim()),
+            None => (None, scope.trim()),
+        };
+        if device.is_some_and(|device| !device.eq_ignore_ascii_case(name) && device != guid) {
+            continue;
+        }
+        let axis = match axis {
+            "*" => None,
+            axis => match axis.parse() {
+                Ok(axis) => Some(axis),
+                Err(_) => {
+                    println!("axes: {} isn't an axis", axis);
+                    continue;
+                }
+            },
+        };
+        let specificity = device.is_some() as u8 + 2 * axis.is_some() as u8;
+        matching.push((specificity, axis, setting.trim(), value.as_str()));
+    }
+    // Stable, so equally specific ones stay in order.
+    matching.sort_by_key(|&(specificity, ..)| specificity);
+
+    let mut settings = vec![AxisSettings::default(); axes];
+    for (_, axis, setting, value) in matching {
+        for (i, axis_settings) in settings.iter_mut().enumerate() {
+            if axis.is_none_or(|axis| axis == i) {
+                axis_settings.set(setting, value);
+            }
+        }
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        let settings = AxisSettings {
+            deadzone: 0.2,
+            outer_deadzone: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(settings.apply(6000), 0);
+        assert_eq!(settings.apply(-32767), -32767);
+        assert_eq!(settings.apply(30000), 32767);
+        assert_eq!(settings.apply(16384), 16384);
+
+        let settings = AxisSettings {
+            invert: true,
+            curve: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(settings.apply(16384), -8192);
+        assert_eq!(settings.apply(0), 0);
+    }
+
+    #[test]
+    fn specificity() {
+        let entries: Vec<(String, String)> = [
+            ("Pad/1.deadzone", "0.5"),
+            ("deadzone", "0.1"),
+            ("1.deadzone", "0.3"),
+            ("Other/*.invert", "true"),
+            ("pad/*.invert", "true"),
+            ("0.invert", "false"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let settings = settings(&entries, "Pad", "0300", 3);
+        assert_eq!(settings[0].deadzone, 0.1);
+        assert!(!settings[0].invert);
+        assert_eq!(settings[1].deadzone, 0.5);
+        assert!(settings[1].invert);
+        assert_eq!(settings[2].deadzone, 0.1);
+    }
+}
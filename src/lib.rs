@@ -13,7 +13,9 @@ mod exports;
 mod type_defs;
 
 mod backend;
+mod config;
 mod egl;
+mod joystick;
 mod xcb;
 
 mod make_mesa_happy;
@@ -410,8 +410,22 @@ pub const SDLKey_SDLK_LAST: SDLKey = 323;
 pub type SDLKey = ::std::os::raw::c_uint;
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct SDL_Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub unused: u8,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SDL_Palette {
+    pub ncolors: ::std::os::raw::c_int,
+    pub colors: *mut SDL_Color,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct SDL_PixelFormat {
-    pub palette: *mut ::std::os::raw::c_void,
+    pub palette: *mut SDL_Palette,
     pub BitsPerPixel: u8,
     pub BytesPerPixel: u8,
     pub Rloss: u8,
@@ -432,11 +446,13 @@ pub struct SDL_PixelFormat {
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct SDL_VideoInfo {
-    pub _bitfield_align_1: [u8; 0],
-    pub _bitfield_1: __BindgenBitfieldUnit<[u8; 1usize]>,
-    pub blit_fill: u32,
+    pub _bitfield_align_1: [u32; 0],
+    pub _bitfield_1: __BindgenBitfieldUnit<[u8; 4usize]>,
     pub video_mem: u32,
     pub vfmt: *mut SDL_PixelFormat,
+    // Only in SDL 1.2.10 and later, older games never read these.
+    pub current_w: ::std::os::raw::c_int,
+    pub current_h: ::std::os::raw::c_int,
 }
 impl SDL_VideoInfo {
     #[inline]
@@ -453,7 +469,7 @@ impl SDL_VideoInfo {
     #[inline]
     pub unsafe fn hw_available_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
                 0usize,
                 1u8,
@@ -464,7 +480,7 @@ impl SDL_VideoInfo {
     pub unsafe fn set_hw_available_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
                 0usize,
                 1u8,
@@ -486,7 +502,7 @@ impl SDL_VideoInfo {
     #[inline]
     pub unsafe fn wm_available_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
                 1usize,
                 1u8,
@@ -497,7 +513,7 @@ impl SDL_VideoInfo {
     pub unsafe fn set_wm_available_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
                 1usize,
                 1u8,
@@ -507,21 +523,21 @@ impl SDL_VideoInfo {
     }
     #[inline]
     pub fn blit_hw(&self) -> u32 {
-        unsafe { ::std::mem::transmute(self._bitfield_1.get(2usize, 1u8) as u32) }
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(9usize, 1u8) as u32) }
     }
     #[inline]
     pub fn set_blit_hw(&mut self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            self._bitfield_1.set(2usize, 1u8, val as u64)
+            self._bitfield_1.set(9usize, 1u8, val as u64)
         }
     }
     #[inline]
     pub unsafe fn blit_hw_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
-                2usize,
+                9usize,
                 1u8,
             ) as u32)
         }
@@ -530,9 +546,9 @@ impl SDL_VideoInfo {
     pub unsafe fn set_blit_hw_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
-                2usize,
+                9usize,
                 1u8,
                 val as u64,
             )
@@ -540,21 +556,21 @@ impl SDL_VideoInfo {
     }
     #[inline]
     pub fn blit_hw_CC(&self) -> u32 {
-        unsafe { ::std::mem::transmute(self._bitfield_1.get(3usize, 1u8) as u32) }
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(10usize, 1u8) as u32) }
     }
     #[inline]
     pub fn set_blit_hw_CC(&mut self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            self._bitfield_1.set(3usize, 1u8, val as u64)
+            self._bitfield_1.set(10usize, 1u8, val as u64)
         }
     }
     #[inline]
     pub unsafe fn blit_hw_CC_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
-                3usize,
+                10usize,
                 1u8,
             ) as u32)
         }
@@ -563,9 +579,9 @@ impl SDL_VideoInfo {
     pub unsafe fn set_blit_hw_CC_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
-                3usize,
+                10usize,
                 1u8,
                 val as u64,
             )
@@ -573,21 +589,21 @@ impl SDL_VideoInfo {
     }
     #[inline]
     pub fn blit_hw_A(&self) -> u32 {
-        unsafe { ::std::mem::transmute(self._bitfield_1.get(4usize, 1u8) as u32) }
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(11usize, 1u8) as u32) }
     }
     #[inline]
     pub fn set_blit_hw_A(&mut self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            self._bitfield_1.set(4usize, 1u8, val as u64)
+            self._bitfield_1.set(11usize, 1u8, val as u64)
         }
     }
     #[inline]
     pub unsafe fn blit_hw_A_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
-                4usize,
+                11usize,
                 1u8,
             ) as u32)
         }
@@ -596,9 +612,9 @@ impl SDL_VideoInfo {
     pub unsafe fn set_blit_hw_A_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
-                4usize,
+                11usize,
                 1u8,
                 val as u64,
             )
@@ -606,21 +622,21 @@ impl SDL_VideoInfo {
     }
     #[inline]
     pub fn blit_sw(&self) -> u32 {
-        unsafe { ::std::mem::transmute(self._bitfield_1.get(5usize, 1u8) as u32) }
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(12usize, 1u8) as u32) }
     }
     #[inline]
     pub fn set_blit_sw(&mut self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            self._bitfield_1.set(5usize, 1u8, val as u64)
+            self._bitfield_1.set(12usize, 1u8, val as u64)
         }
     }
     #[inline]
     pub unsafe fn blit_sw_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
-                5usize,
+                12usize,
                 1u8,
             ) as u32)
         }
@@ -629,9 +645,9 @@ impl SDL_VideoInfo {
     pub unsafe fn set_blit_sw_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
-                5usize,
+                12usize,
                 1u8,
                 val as u64,
             )
@@ -639,21 +655,21 @@ impl SDL_VideoInfo {
     }
     #[inline]
     pub fn blit_sw_CC(&self) -> u32 {
-        unsafe { ::std::mem::transmute(self._bitfield_1.get(6usize, 1u8) as u32) }
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(13usize, 1u8) as u32) }
     }
     #[inline]
     pub fn set_blit_sw_CC(&mut self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            self._bitfield_1.set(6usize, 1u8, val as u64)
+            self._bitfield_1.set(13usize, 1u8, val as u64)
         }
     }
     #[inline]
     pub unsafe fn blit_sw_CC_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
-                6usize,
+                13usize,
                 1u8,
             ) as u32)
         }
@@ -662,9 +678,9 @@ impl SDL_VideoInfo {
     pub unsafe fn set_blit_sw_CC_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
-                6usize,
+                13usize,
                 1u8,
                 val as u64,
             )
@@ -672,21 +688,21 @@ impl SDL_VideoInfo {
     }
     #[inline]
     pub fn blit_sw_A(&self) -> u32 {
-        unsafe { ::std::mem::transmute(self._bitfield_1.get(7usize, 1u8) as u32) }
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(14usize, 1u8) as u32) }
     }
     #[inline]
     pub fn set_blit_sw_A(&mut self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            self._bitfield_1.set(7usize, 1u8, val as u64)
+            self._bitfield_1.set(14usize, 1u8, val as u64)
         }
     }
     #[inline]
     pub unsafe fn blit_sw_A_raw(this: *const Self) -> u32 {
         unsafe {
-            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 1usize]>>::raw_get(
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
                 ::std::ptr::addr_of!((*this)._bitfield_1),
-                7usize,
+                14usize,
                 1u8,
             ) as u32)
         }
@@ -695,9 +711,42 @@ impl SDL_VideoInfo {
     pub unsafe fn set_blit_sw_A_raw(this: *mut Self, val: u32) {
         unsafe {
             let val: u32 = ::std::mem::transmute(val);
-            <__BindgenBitfieldUnit<[u8; 1usize]>>::raw_set(
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
+                ::std::ptr::addr_of_mut!((*this)._bitfield_1),
+                14usize,
+                1u8,
+                val as u64,
+            )
+        }
+    }
+    #[inline]
+    pub fn blit_fill(&self) -> u32 {
+        unsafe { ::std::mem::transmute(self._bitfield_1.get(15usize, 1u8) as u32) }
+    }
+    #[inline]
+    pub fn set_blit_fill(&mut self, val: u32) {
+        unsafe {
+            let val: u32 = ::std::mem::transmute(val);
+            self._bitfield_1.set(15usize, 1u8, val as u64)
+        }
+    }
+    #[inline]
+    pub unsafe fn blit_fill_raw(this: *const Self) -> u32 {
+        unsafe {
+            ::std::mem::transmute(<__BindgenBitfieldUnit<[u8; 4usize]>>::raw_get(
+                ::std::ptr::addr_of!((*this)._bitfield_1),
+                15usize,
+                1u8,
+            ) as u32)
+        }
+    }
+    #[inline]
+    pub unsafe fn set_blit_fill_raw(this: *mut Self, val: u32) {
+        unsafe {
+            let val: u32 = ::std::mem::transmute(val);
+            <__BindgenBitfieldUnit<[u8; 4usize]>>::raw_set(
                 ::std::ptr::addr_of_mut!((*this)._bitfield_1),
-                7usize,
+                15usize,
                 1u8,
                 val as u64,
             )
@@ -713,8 +762,9 @@ impl SDL_VideoInfo {
         blit_sw: u32,
         blit_sw_CC: u32,
         blit_sw_A: u32,
-    ) -> __BindgenBitfieldUnit<[u8; 1usize]> {
-        let mut __bindgen_bitfield_unit: __BindgenBitfieldUnit<[u8; 1usize]> = Default::default();
+        blit_fill: u32,
+    ) -> __BindgenBitfieldUnit<[u8; 4usize]> {
+        let mut __bindgen_bitfield_unit: __BindgenBitfieldUnit<[u8; 4usize]> = Default::default();
         __bindgen_bitfield_unit.set(0usize, 1u8, {
             let hw_available: u32 = unsafe { ::std::mem::transmute(hw_available) };
             hw_available as u64
@@ -723,40 +773,44 @@ impl SDL_VideoInfo {
             let wm_available: u32 = unsafe { ::std::mem::transmute(wm_available) };
             wm_available as u64
         });
-        __bindgen_bitfield_unit.set(2usize, 1u8, {
+        __bindgen_bitfield_unit.set(9usize, 1u8, {
             let blit_hw: u32 = unsafe { ::std::mem::transmute(blit_hw) };
             blit_hw as u64
         });
-        __bindgen_bitfield_unit.set(3usize, 1u8, {
+        __bindgen_bitfield_unit.set(10usize, 1u8, {
             let blit_hw_CC: u32 = unsafe { ::std::mem::transmute(blit_hw_CC) };
             blit_hw_CC as u64
         });
-        __bindgen_bitfield_unit.set(4usize, 1u8, {
+        __bindgen_bitfield_unit.set(11usize, 1u8, {
             let blit_hw_A: u32 = unsafe { ::std::mem::transmute(blit_hw_A) };
             blit_hw_A as u64
         });
-        __bindgen_bitfield_unit.set(5usize, 1u8, {
+        __bindgen_bitfield_unit.set(12usize, 1u8, {
             let blit_sw: u32 = unsafe { ::std::mem::transmute(blit_sw) };
             blit_sw as u64
         });
-        __bindgen_bitfield_unit.set(6usize, 1u8, {
+        __bindgen_bitfield_unit.set(13usize, 1u8, {
             let blit_sw_CC: u32 = unsafe { ::std::mem::transmute(blit_sw_CC) };
             blit_sw_CC as u64
         });
-        __bindgen_bitfield_unit.set(7usize, 1u8, {
+        __bindgen_bitfield_unit.set(14usize, 1u8, {
             let blit_sw_A: u32 = unsafe { ::std::mem::transmute(blit_sw_A) };
             blit_sw_A as u64
         });
+        __bindgen_bitfield_unit.set(15usize, 1u8, {
+            let blit_fill: u32 = unsafe { ::std::mem::transmute(blit_fill) };
+            blit_fill as u64
+        });
         __bindgen_bitfield_unit
     }
 }
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct SDL_Rect {
-    pub x: ::std::os::raw::c_int,
-    pub y: ::std::os::raw::c_int,
-    pub w: ::std::os::raw::c_int,
-    pub h: ::std::os::raw::c_int,
+    pub x: i16,
+    pub y: i16,
+    pub w: u16,
+    pub h: u16,
 }
 
 #[derive(Debug)]
@@ -941,6 +995,23 @@ pub struct SDL_RWops {
     pub padding: [u8; 12],
 }
 
+pub const SDL_SRCCOLORKEY: u32 = 0x00001000;
+pub const SDL_FULLSCREEN: u32 = 0x80000000;
+pub const SDL_BUTTON_LEFT: u8 = 1;
+pub const SDL_BUTTON_MIDDLE: u8 = 2;
+pub const SDL_BUTTON_RIGHT: u8 = 3;
+pub const SDL_BUTTON_WHEELUP: u8 = 4;
+pub const SDL_BUTTON_WHEELDOWN: u8 = 5;
+pub const SDL_BUTTON_X1: u8 = 6;
+pub const SDL_BUTTON_X2: u8 = 7;
+pub const SDL_HAT_CENTERED: u8 = 0x00;
+pub const SDL_HAT_UP: u8 = 0x01;
+pub const SDL_HAT_RIGHT: u8 = 0x02;
+pub const SDL_HAT_DOWN: u8 = 0x04;
+pub const SDL_HAT_LEFT: u8 = 0x08;
+pub const SDL_QUERY: i32 = -1;
+pub const SDL_IGNORE: i32 = 0;
+pub const SDL_ENABLE: i32 = 1;
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct SDL_Surface {
@@ -1,130 +1,426 @@
+use std::{
+    ffi::{CStr, CString},
+    sync::LazyLock,
+};
+
 use crate::type_defs::{
-    SDL_keysym, SDLKey, SDLKey_SDLK_0, SDLKey_SDLK_1, SDLKey_SDLK_2, SDLKey_SDLK_3, SDLKey_SDLK_4,
+    SDLKey, SDLKey_SDLK_0, SDLKey_SDLK_1, SDLKey_SDLK_2, SDLKey_SDLK_3, SDLKey_SDLK_4,
     SDLKey_SDLK_5, SDLKey_SDLK_6, SDLKey_SDLK_7, SDLKey_SDLK_8, SDLKey_SDLK_9,
-    SDLKey_SDLK_BACKSLASH, SDLKey_SDLK_BACKSPACE, SDLKey_SDLK_CAPSLOCK, SDLKey_SDLK_DELETE,
-    SDLKey_SDLK_DOWN, SDLKey_SDLK_END, SDLKey_SDLK_EQUALS, SDLKey_SDLK_ESCAPE, SDLKey_SDLK_F1,
+    SDLKey_SDLK_AMPERSAND, SDLKey_SDLK_ASTERISK, SDLKey_SDLK_AT, SDLKey_SDLK_BACKQUOTE,
+    SDLKey_SDLK_BACKSLASH, SDLKey_SDLK_BACKSPACE, SDLKey_SDLK_BREAK, SDLKey_SDLK_CAPSLOCK,
+    SDLKey_SDLK_CARET, SDLKey_SDLK_CLEAR, SDLKey_SDLK_COLON, SDLKey_SDLK_COMMA,
+    SDLKey_SDLK_COMPOSE, SDLKey_SDLK_DELETE, SDLKey_SDLK_DOLLAR, SDLKey_SDLK_DOWN, SDLKey_SDLK_END,
+    SDLKey_SDLK_EQUALS, SDLKey_SDLK_ESCAPE, SDLKey_SDLK_EURO, SDLKey_SDLK_EXCLAIM, SDLKey_SDLK_F1,
     SDLKey_SDLK_F2, SDLKey_SDLK_F3, SDLKey_SDLK_F4, SDLKey_SDLK_F5, SDLKey_SDLK_F6, SDLKey_SDLK_F7,
     SDLKey_SDLK_F8, SDLKey_SDLK_F9, SDLKey_SDLK_F10, SDLKey_SDLK_F11, SDLKey_SDLK_F12,
-    SDLKey_SDLK_HOME, SDLKey_SDLK_INSERT, SDLKey_SDLK_KP_DIVIDE, SDLKey_SDLK_KP_ENTER,
-    SDLKey_SDLK_KP_EQUALS, SDLKey_SDLK_KP_MINUS, SDLKey_SDLK_KP_MULTIPLY, SDLKey_SDLK_KP_PERIOD,
-    SDLKey_SDLK_KP_PLUS, SDLKey_SDLK_KP0, SDLKey_SDLK_KP1, SDLKey_SDLK_KP2, SDLKey_SDLK_KP3,
-    SDLKey_SDLK_KP4, SDLKey_SDLK_KP5, SDLKey_SDLK_KP6, SDLKey_SDLK_KP7, SDLKey_SDLK_KP8,
-    SDLKey_SDLK_KP9, SDLKey_SDLK_LALT, SDLKey_SDLK_LCTRL, SDLKey_SDLK_LEFT,
-    SDLKey_SDLK_LEFTBRACKET, SDLKey_SDLK_LSHIFT, SDLKey_SDLK_LSUPER, SDLKey_SDLK_NUMLOCK,
-    SDLKey_SDLK_PAGEDOWN, SDLKey_SDLK_PAGEUP, SDLKey_SDLK_PAUSE, SDLKey_SDLK_PRINT,
-    SDLKey_SDLK_RALT, SDLKey_SDLK_RCTRL, SDLKey_SDLK_RETURN, SDLKey_SDLK_RIGHT,
-    SDLKey_SDLK_RIGHTBRACKET, SDLKey_SDLK_RSHIFT, SDLKey_SDLK_RSUPER, SDLKey_SDLK_SCROLLOCK,
-    SDLKey_SDLK_SEMICOLON, SDLKey_SDLK_SPACE, SDLKey_SDLK_TAB, SDLKey_SDLK_UNKNOWN, SDLKey_SDLK_UP,
-    SDLKey_SDLK_a, SDLKey_SDLK_b, SDLKey_SDLK_c, SDLKey_SDLK_d, SDLKey_SDLK_e, SDLKey_SDLK_f,
-    SDLKey_SDLK_g, SDLKey_SDLK_h, SDLKey_SDLK_i, SDLKey_SDLK_j, SDLKey_SDLK_k, SDLKey_SDLK_l,
-    SDLKey_SDLK_m, SDLKey_SDLK_n, SDLKey_SDLK_o, SDLKey_SDLK_p, SDLKey_SDLK_q, SDLKey_SDLK_r,
-    SDLKey_SDLK_s, SDLKey_SDLK_t, SDLKey_SDLK_u, SDLKey_SDLK_v, SDLKey_SDLK_w, SDLKey_SDLK_x,
-    SDLKey_SDLK_y, SDLKey_SDLK_z,
+    SDLKey_SDLK_F13, SDLKey_SDLK_F14, SDLKey_SDLK_F15, SDLKey_SDLK_GREATER, SDLKey_SDLK_HASH,
+    SDLKey_SDLK_HELP, SDLKey_SDLK_HOME, SDLKey_SDLK_INSERT, SDLKey_SDLK_KP_DIVIDE,
+    SDLKey_SDLK_KP_ENTER, SDLKey_SDLK_KP_EQUALS, SDLKey_SDLK_KP_MINUS, SDLKey_SDLK_KP_MULTIPLY,
+    SDLKey_SDLK_KP_PERIOD, SDLKey_SDLK_KP_PLUS, SDLKey_SDLK_KP0, SDLKey_SDLK_KP1, SDLKey_SDLK_KP2,
+    SDLKey_SDLK_KP3, SDLKey_SDLK_KP4, SDLKey_SDLK_KP5, SDLKey_SDLK_KP6, SDLKey_SDLK_KP7,
+    SDLKey_SDLK_KP8, SDLKey_SDLK_KP9, SDLKey_SDLK_LALT, SDLKey_SDLK_LCTRL, SDLKey_SDLK_LEFT,
+    SDLKey_SDLK_LEFTBRACKET, SDLKey_SDLK_LEFTPAREN, SDLKey_SDLK_LESS, SDLKey_SDLK_LMETA,
+    SDLKey_SDLK_LSHIFT, SDLKey_SDLK_LSUPER, SDLKey_SDLK_MENU, SDLKey_SDLK_MINUS, SDLKey_SDLK_MODE,
+    SDLKey_SDLK_NUMLOCK, SDLKey_SDLK_PAGEDOWN, SDLKey_SDLK_PAGEUP, SDLKey_SDLK_PAUSE,
+    SDLKey_SDLK_PERIOD, SDLKey_SDLK_PLUS, SDLKey_SDLK_POWER, SDLKey_SDLK_PRINT,
+    SDLKey_SDLK_QUESTION, SDLKey_SDLK_QUOTE, SDLKey_SDLK_QUOTEDBL, SDLKey_SDLK_RALT,
+    SDLKey_SDLK_RCTRL, SDLKey_SDLK_RETURN, SDLKey_SDLK_RIGHT, SDLKey_SDLK_RIGHTBRACKET,
+    SDLKey_SDLK_RIGHTPAREN, SDLKey_SDLK_RMETA, SDLKey_SDLK_RSHIFT, SDLKey_SDLK_RSUPER,
+    SDLKey_SDLK_SCROLLOCK, SDLKey_SDLK_SEMICOLON, SDLKey_SDLK_SLASH, SDLKey_SDLK_SPACE,
+    SDLKey_SDLK_SYSREQ, SDLKey_SDLK_TAB, SDLKey_SDLK_UNDERSCORE, SDLKey_SDLK_UNDO,
+    SDLKey_SDLK_UNKNOWN, SDLKey_SDLK_UP, SDLKey_SDLK_WORLD_0, SDLKey_SDLK_WORLD_95, SDLKey_SDLK_a,
+    SDLKey_SDLK_b, SDLKey_SDLK_c, SDLKey_SDLK_d, SDLKey_SDLK_e, SDLKey_SDLK_f, SDLKey_SDLK_g,
+    SDLKey_SDLK_h, SDLKey_SDLK_i, SDLKey_SDLK_j, SDLKey_SDLK_k, SDLKey_SDLK_l, SDLKey_SDLK_m,
+    SDLKey_SDLK_n, SDLKey_SDLK_o, SDLKey_SDLK_p, SDLKey_SDLK_q, SDLKey_SDLK_r, SDLKey_SDLK_s,
+    SDLKey_SDLK_t, SDLKey_SDLK_u, SDLKey_SDLK_v, SDLKey_SDLK_w, SDLKey_SDLK_x, SDLKey_SDLK_y,
+    SDLKey_SDLK_z,
 };
 use xkeysym::Keysym;
 
+// Everything SDL 1.2's X11 driver knows how to translate, minus Latin-1, which is handled below.
+const KEYSYMS: &[(Keysym, SDLKey)] = &[
+    (Keysym::BackSpace, SDLKey_SDLK_BACKSPACE),
+    (Keysym::Tab, SDLKey_SDLK_TAB),
+    (Keysym::ISO_Left_Tab, SDLKey_SDLK_TAB),
+    (Keysym::Clear, SDLKey_SDLK_CLEAR),
+    (Keysym::Return, SDLKey_SDLK_RETURN),
+    (Keysym::Pause, SDLKey_SDLK_PAUSE),
+    (Keysym::Escape, SDLKey_SDLK_ESCAPE),
+    (Keysym::space, SDLKey_SDLK_SPACE),
+    (Keysym::exclam, SDLKey_SDLK_EXCLAIM),
+    (Keysym::quotedbl, SDLKey_SDLK_QUOTEDBL),
+    (Keysym::numbersign, SDLKey_SDLK_HASH),
+    (Keysym::dollar, SDLKey_SDLK_DOLLAR),
+    (Keysym::ampersand, SDLKey_SDLK_AMPERSAND),
+    (Keysym::apostrophe, SDLKey_SDLK_QUOTE),
+    (Keysym::parenleft, SDLKey_SDLK_LEFTPAREN),
+    (Keysym::parenright, SDLKey_SDLK_RIGHTPAREN),
+    (Keysym::asterisk, SDLKey_SDLK_ASTERISK),
+    (Keysym::plus, SDLKey_SDLK_PLUS),
+    (Keysym::comma, SDLKey_SDLK_COMMA),
+    (Keysym::minus, SDLKey_SDLK_MINUS),
+    (Keysym::period, SDLKey_SDLK_PERIOD),
+    (Keysym::slash, SDLKey_SDLK_SLASH),
+    (Keysym::_0, SDLKey_SDLK_0),
+    (Keysym::_1, SDLKey_SDLK_1),
+    (Keysym::_2, SDLKey_SDLK_2),
+    (Keysym::_3, SDLKey_SDLK_3),
+    (Keysym::_4, SDLKey_SDLK_4),
+    (Keysym::_5, SDLKey_SDLK_5),
+    (Keysym::_6, SDLKey_SDLK_6),
+    (Keysym::_7, SDLKey_SDLK_7),
+    (Keysym::_8, SDLKey_SDLK_8),
+    (Keysym::_9, SDLKey_SDLK_9),
+    (Keysym::colon, SDLKey_SDLK_COLON),
+    (Keysym::semicolon, SDLKey_SDLK_SEMICOLON),
+    (Keysym::less, SDLKey_SDLK_LESS),
+    (Keysym::equal, SDLKey_SDLK_EQUALS),
+    (Keysym::greater, SDLKey_SDLK_GREATER),
+    (Keysym::question, SDLKey_SDLK_QUESTION),
+    (Keysym::at, SDLKey_SDLK_AT),
+    (Keysym::bracketleft, SDLKey_SDLK_LEFTBRACKET),
+    (Keysym::backslash, SDLKey_SDLK_BACKSLASH),
+    (Keysym::bracketright, SDLKey_SDLK_RIGHTBRACKET),
+    (Keysym::asciicircum, SDLKey_SDLK_CARET),
+    (Keysym::underscore, SDLKey_SDLK_UNDERSCORE),
+    (Keysym::grave, SDLKey_SDLK_BACKQUOTE),
+    (Keysym::a, SDLKey_SDLK_a),
+    (Keysym::b, SDLKey_SDLK_b),
+    (Keysym::c, SDLKey_SDLK_c),
+    (Keysym::d, SDLKey_SDLK_d),
+    (Keysym::e, SDLKey_SDLK_e),
+    (Keysym::f, SDLKey_SDLK_f),
+    (Keysym::g, SDLKey_SDLK_g),
+    (Keysym::h, SDLKey_SDLK_h),
+    (Keysym::i, SDLKey_SDLK_i),
+    (Keysym::j, SDLKey_SDLK_j),
+    (Keysym::k, SDLKey_SDLK_k),
+    (Keysym::l, SDLKey_SDLK_l),
+    (Keysym::m, SDLKey_SDLK_m),
+    (Keysym::n, SDLKey_SDLK_n),
+    (Keysym::o, SDLKey_SDLK_o),
+    (Keysym::p, SDLKey_SDLK_p),
+    (Keysym::q, SDLKey_SDLK_q),
+    (Keysym::r, SDLKey_SDLK_r),
+    (Keysym::s, SDLKey_SDLK_s),
+    (Keysym::t, SDLKey_SDLK_t),
+    (Keysym::u, SDLKey_SDLK_u),
+    (Keysym::v, SDLKey_SDLK_v),
+    (Keysym::w, SDLKey_SDLK_w),
+    (Keysym::x, SDLKey_SDLK_x),
+    (Keysym::y, SDLKey_SDLK_y),
+    (Keysym::z, SDLKey_SDLK_z),
+    (Keysym::Delete, SDLKey_SDLK_DELETE),
+    (Keysym::KP_0, SDLKey_SDLK_KP0),
+    (Keysym::KP_1, SDLKey_SDLK_KP1),
+    (Keysym::KP_2, SDLKey_SDLK_KP2),
+    (Keysym::KP_3, SDLKey_SDLK_KP3),
+    (Keysym::KP_4, SDLKey_SDLK_KP4),
+    (Keysym::KP_5, SDLKey_SDLK_KP5),
+    (Keysym::KP_6, SDLKey_SDLK_KP6),
+    (Keysym::KP_7, SDLKey_SDLK_KP7),
+    (Keysym::KP_8, SDLKey_SDLK_KP8),
+    (Keysym::KP_9, SDLKey_SDLK_KP9),
+    (Keysym::KP_Decimal, SDLKey_SDLK_KP_PERIOD),
+    (Keysym::KP_Separator, SDLKey_SDLK_KP_PERIOD),
+    (Keysym::KP_Divide, SDLKey_SDLK_KP_DIVIDE),
+    (Keysym::KP_Multiply, SDLKey_SDLK_KP_MULTIPLY),
+    (Keysym::KP_Subtract, SDLKey_SDLK_KP_MINUS),
+    (Keysym::KP_Add, SDLKey_SDLK_KP_PLUS),
+    (Keysym::KP_Enter, SDLKey_SDLK_KP_ENTER),
+    (Keysym::KP_Equal, SDLKey_SDLK_KP_EQUALS),
+    // With num lock off.
+    (Keysym::KP_Insert, SDLKey_SDLK_KP0),
+    (Keysym::KP_End, SDLKey_SDLK_KP1),
+    (Keysym::KP_Down, SDLKey_SDLK_KP2),
+    (Keysym::KP_Page_Down, SDLKey_SDLK_KP3),
+    (Keysym::KP_Left, SDLKey_SDLK_KP4),
+    (Keysym::KP_Begin, SDLKey_SDLK_KP5),
+    (Keysym::KP_Right, SDLKey_SDLK_KP6),
+    (Keysym::KP_Home, SDLKey_SDLK_KP7),
+    (Keysym::KP_Up, SDLKey_SDLK_KP8),
+    (Keysym::KP_Page_Up, SDLKey_SDLK_KP9),
+    (Keysym::KP_Delete, SDLKey_SDLK_KP_PERIOD),
+    (Keysym::Up, SDLKey_SDLK_UP),
+    (Keysym::Down, SDLKey_SDLK_DOWN),
+    (Keysym::Right, SDLKey_SDLK_RIGHT),
+    (Keysym::Left, SDLKey_SDLK_LEFT),
+    (Keysym::Insert, SDLKey_SDLK_INSERT),
+    (Keysym::Home, SDLKey_SDLK_HOME),
+    (Keysym::End, SDLKey_SDLK_END),
+    (Keysym::Page_Up, SDLKey_SDLK_PAGEUP),
+    (Keysym::Page_Down, SDLKey_SDLK_PAGEDOWN),
+    (Keysym::F1, SDLKey_SDLK_F1),
+    (Keysym::F2, SDLKey_SDLK_F2),
+    (Keysym::F3, SDLKey_SDLK_F3),
+    (Keysym::F4, SDLKey_SDLK_F4),
+    (Keysym::F5, SDLKey_SDLK_F5),
+    (Keysym::F6, SDLKey_SDLK_F6),
+    (Keysym::F7, SDLKey_SDLK_F7),
+    (Keysym::F8, SDLKey_SDLK_F8),
+    (Keysym::F9, SDLKey_SDLK_F9),
+    (Keysym::F10, SDLKey_SDLK_F10),
+    (Keysym::F11, SDLKey_SDLK_F11),
+    (Keysym::F12, SDLKey_SDLK_F12),
+    (Keysym::F13, SDLKey_SDLK_F13),
+    (Keysym::F14, SDLKey_SDLK_F14),
+    (Keysym::F15, SDLKey_SDLK_F15),
+    (Keysym::Num_Lock, SDLKey_SDLK_NUMLOCK),
+    (Keysym::Caps_Lock, SDLKey_SDLK_CAPSLOCK),
+    (Keysym::Scroll_Lock, SDLKey_SDLK_SCROLLOCK),
+    (Keysym::Shift_R, SDLKey_SDLK_RSHIFT),
+    (Keysym::Shift_L, SDLKey_SDLK_LSHIFT),
+    (Keysym::Control_R, SDLKey_SDLK_RCTRL),
+    (Keysym::Control_L, SDLKey_SDLK_LCTRL),
+    (Keysym::Alt_R, SDLKey_SDLK_RALT),
+    (Keysym::Alt_L, SDLKey_SDLK_LALT),
+    // AltGr on pretty much every layout that has one. SDL 1.2 calls it MODE, not RALT, so games
+    // can tell it apart from the real Alt.
+    (Keysym::ISO_Level3_Shift, SDLKey_SDLK_MODE),
+    (Keysym::Meta_R, SDLKey_SDLK_RMETA),
+    (Keysym::Meta_L, SDLKey_SDLK_LMETA),
+    (Keysym::Super_L, SDLKey_SDLK_LSUPER),
+    (Keysym::Super_R, SDLKey_SDLK_RSUPER),
+    (Keysym::Mode_switch, SDLKey_SDLK_MODE),
+    (Keysym::Multi_key, SDLKey_SDLK_COMPOSE),
+    (Keysym::Help, SDLKey_SDLK_HELP),
+    (Keysym::Print, SDLKey_SDLK_PRINT),
+    (Keysym::Sys_Req, SDLKey_SDLK_SYSREQ),
+    (Keysym::Break, SDLKey_SDLK_BREAK),
+    (Keysym::Menu, SDLKey_SDLK_MENU),
+    (Keysym::XF86_PowerOff, SDLKey_SDLK_POWER),
+    (Keysym::EuroSign, SDLKey_SDLK_EURO),
+    (Keysym::Undo, SDLKey_SDLK_UNDO),
+];
+
 pub fn sdl_key_from_keysym(sym: Keysym) -> SDLKey {
-    match sym {
-        Keysym::_0 => SDLKey_SDLK_0,
-        Keysym::_1 => SDLKey_SDLK_1,
-        Keysym::_2 => SDLKey_SDLK_2,
-        Keysym::_3 => SDLKey_SDLK_3,
-        Keysym::_4 => SDLKey_SDLK_4,
-        Keysym::_5 => SDLKey_SDLK_5,
-        Keysym::_6 => SDLKey_SDLK_6,
-        Keysym::_7 => SDLKey_SDLK_7,
-        Keysym::_8 => SDLKey_SDLK_8,
-        Keysym::_9 => SDLKey_SDLK_9,
-        Keysym::semicolon => SDLKey_SDLK_SEMICOLON,
-        Keysym::equal => SDLKey_SDLK_EQUALS,
-        Keysym::A => SDLKey_SDLK_a,
-        Keysym::B => SDLKey_SDLK_b,
-        Keysym::C => SDLKey_SDLK_c,
-        Keysym::D => SDLKey_SDLK_d,
-        Keysym::E => SDLKey_SDLK_e,
-        Keysym::F => SDLKey_SDLK_f,
-        Keysym::G => SDLKey_SDLK_g,
-        Keysym::H => SDLKey_SDLK_h,
-        Keysym::I => SDLKey_SDLK_i,
-        Keysym::J => SDLKey_SDLK_j,
-        Keysym::K => SDLKey_SDLK_k,
-        Keysym::L => SDLKey_SDLK_l,
-        Keysym::M => SDLKey_SDLK_m,
-        Keysym::N => SDLKey_SDLK_n,
-        Keysym::O => SDLKey_SDLK_o,
-        Keysym::P => SDLKey_SDLK_p,
-        Keysym::Q => SDLKey_SDLK_q,
-        Keysym::R => SDLKey_SDLK_r,
-        Keysym::S => SDLKey_SDLK_s,
-        Keysym::T => SDLKey_SDLK_t,
-        Keysym::U => SDLKey_SDLK_u,
-        Keysym::V => SDLKey_SDLK_v,
-        Keysym::W => SDLKey_SDLK_w,
-        Keysym::X => SDLKey_SDLK_x,
-        Keysym::Y => SDLKey_SDLK_y,
-        Keysym::Z => SDLKey_SDLK_z,
-        Keysym::bracketleft => SDLKey_SDLK_LEFTBRACKET,
-        Keysym::backslash => SDLKey_SDLK_BACKSLASH,
-        Keysym::bracketright => SDLKey_SDLK_RIGHTBRACKET,
-        // Keysym::grave => SDLKey_SDLK_GRAVE,
-        Keysym::space => SDLKey_SDLK_SPACE,
-        Keysym::Escape => SDLKey_SDLK_ESCAPE,
-        Keysym::Return => SDLKey_SDLK_RETURN,
-        Keysym::Tab => SDLKey_SDLK_TAB,
-        Keysym::BackSpace => SDLKey_SDLK_BACKSPACE,
-        Keysym::Insert => SDLKey_SDLK_INSERT,
-        Keysym::Delete => SDLKey_SDLK_DELETE,
-        Keysym::Right => SDLKey_SDLK_RIGHT,
-        Keysym::Left => SDLKey_SDLK_LEFT,
-        Keysym::Down => SDLKey_SDLK_DOWN,
-        Keysym::Up => SDLKey_SDLK_UP,
-        Keysym::Page_Up => SDLKey_SDLK_PAGEUP,
-        Keysym::Page_Down => SDLKey_SDLK_PAGEDOWN,
-        Keysym::Home => SDLKey_SDLK_HOME,
-        Keysym::End => SDLKey_SDLK_END,
-        Keysym::Caps_Lock => SDLKey_SDLK_CAPSLOCK,
-        Keysym::Scroll_Lock => SDLKey_SDLK_SCROLLOCK,
-        Keysym::Num_Lock => SDLKey_SDLK_NUMLOCK,
-        Keysym::_3270_PrintScreen => SDLKey_SDLK_PRINT,
-        Keysym::Pause => SDLKey_SDLK_PAUSE,
-        Keysym::F1 => SDLKey_SDLK_F1,
-        Keysym::F2 => SDLKey_SDLK_F2,
-        Keysym::F3 => SDLKey_SDLK_F3,
-        Keysym::F4 => SDLKey_SDLK_F4,
-        Keysym::F5 => SDLKey_SDLK_F5,
-        Keysym::F6 => SDLKey_SDLK_F6,
-        Keysym::F7 => SDLKey_SDLK_F7,
-        Keysym::F8 => SDLKey_SDLK_F8,
-        Keysym::F9 => SDLKey_SDLK_F9,
-        Keysym::F10 => SDLKey_SDLK_F10,
-        Keysym::F11 => SDLKey_SDLK_F11,
-        Keysym::F12 => SDLKey_SDLK_F12,
-        Keysym::Shift_L => SDLKey_SDLK_LSHIFT,
-        Keysym::Control_L => SDLKey_SDLK_LCTRL,
-        Keysym::Alt_L => SDLKey_SDLK_LALT,
-        Keysym::Super_L => SDLKey_SDLK_LSUPER,
-        Keysym::Shift_R => SDLKey_SDLK_RSHIFT,
-        Keysym::Control_R => SDLKey_SDLK_RCTRL,
-        Keysym::Alt_R => SDLKey_SDLK_RALT,
-        Keysym::Super_R => SDLKey_SDLK_RSUPER,
-        // Keysym::Menu => SDLKey_SDLK_KP_,
-        Keysym::KP_0 => SDLKey_SDLK_KP0,
-        Keysym::KP_1 => SDLKey_SDLK_KP1,
-        Keysym::KP_2 => SDLKey_SDLK_KP2,
-        Keysym::KP_3 => SDLKey_SDLK_KP3,
-        Keysym::KP_4 => SDLKey_SDLK_KP4,
-        Keysym::KP_5 => SDLKey_SDLK_KP5,
-        Keysym::KP_6 => SDLKey_SDLK_KP6,
-        Keysym::KP_7 => SDLKey_SDLK_KP7,
-        Keysym::KP_8 => SDLKey_SDLK_KP8,
-        Keysym::KP_9 => SDLKey_SDLK_KP9,
-        Keysym::KP_Decimal => SDLKey_SDLK_KP_PERIOD,
-        Keysym::KP_Divide => SDLKey_SDLK_KP_DIVIDE,
-        Keysym::KP_Multiply => SDLKey_SDLK_KP_MULTIPLY,
-        Keysym::KP_Subtract => SDLKey_SDLK_KP_MINUS,
-        Keysym::KP_Add => SDLKey_SDLK_KP_PLUS,
-        Keysym::KP_Enter => SDLKey_SDLK_KP_ENTER,
-        Keysym::KP_Equal => SDLKey_SDLK_KP_EQUALS,
-        _ => SDLKey_SDLK_UNKNOWN,
+    // Latin-1 letters have the same values as the SDLK_WORLD_* keys, same as in SDL.
+    if (SDLKey_SDLK_WORLD_0..=SDLKey_SDLK_WORLD_95).contains(&sym.raw()) {
+        return sym.raw();
+    }
+    // Dead keys are on the base level of plenty of layouts, SDL 1.2 reports them all as compose.
+    if (Keysym::dead_grave.raw()..=Keysym::dead_longsolidusoverlay.raw()).contains(&sym.raw()) {
+        return SDLKey_SDLK_COMPOSE;
+    }
+    KEYSYMS
+        .iter()
+        .find(|(keysym, _)| *keysym == sym)
+        .map_or(SDLKey_SDLK_UNKNOWN, |(_, key)| *key)
+}
+
+// The names SDL_GetKeyName gives, which games show on their key binding screens.
+const KEY_NAMES: &[(SDLKey, &CStr)] = &[
+    (SDLKey_SDLK_BACKSPACE, c"backspace"),
+    (SDLKey_SDLK_TAB, c"tab"),
+    (SDLKey_SDLK_CLEAR, c"clear"),
+    (SDLKey_SDLK_RETURN, c"return"),
+    (SDLKey_SDLK_PAUSE, c"pause"),
+    (SDLKey_SDLK_ESCAPE, c"escape"),
+    (SDLKey_SDLK_SPACE, c"space"),
+    (SDLKey_SDLK_EXCLAIM, c"!"),
+    (SDLKey_SDLK_QUOTEDBL, c"\""),
+    (SDLKey_SDLK_HASH, c"#"),
+    (SDLKey_SDLK_DOLLAR, c"$"),
+    (SDLKey_SDLK_AMPERSAND, c"&"),
+    (SDLKey_SDLK_QUOTE, c"'"),
+    (SDLKey_SDLK_LEFTPAREN, c"("),
+    (SDLKey_SDLK_RIGHTPAREN, c")"),
+    (SDLKey_SDLK_ASTERISK, c"*"),
+    (SDLKey_SDLK_PLUS, c"+"),
+    (SDLKey_SDLK_COMMA, c","),
+    (SDLKey_SDLK_MINUS, c"-"),
+    (SDLKey_SDLK_PERIOD, c"."),
+    (SDLKey_SDLK_SLASH, c"/"),
+    (SDLKey_SDLK_0, c"0"),
+    (SDLKey_SDLK_1, c"1"),
+    (SDLKey_SDLK_2, c"2"),
+    (SDLKey_SDLK_3, c"3"),
+    (SDLKey_SDLK_4, c"4"),
+    (SDLKey_SDLK_5, c"5"),
+    (SDLKey_SDLK_6, c"6"),
+    (SDLKey_SDLK_7, c"7"),
+    (SDLKey_SDLK_8, c"8"),
+    (SDLKey_SDLK_9, c"9"),
+    (SDLKey_SDLK_COLON, c":"),
+    (SDLKey_SDLK_SEMICOLON, c";"),
+    (SDLKey_SDLK_LESS, c"<"),
+    (SDLKey_SDLK_EQUALS, c"="),
+    (SDLKey_SDLK_GREATER, c">"),
+    (SDLKey_SDLK_QUESTION, c"?"),
+    (SDLKey_SDLK_AT, c"@"),
+    (SDLKey_SDLK_LEFTBRACKET, c"["),
+    (SDLKey_SDLK_BACKSLASH, c"\\"),
+    (SDLKey_SDLK_RIGHTBRACKET, c"]"),
+    (SDLKey_SDLK_CARET, c"^"),
+    (SDLKey_SDLK_UNDERSCORE, c"_"),
+    (SDLKey_SDLK_BACKQUOTE, c"`"),
+    (SDLKey_SDLK_a, c"a"),
+    (SDLKey_SDLK_b, c"b"),
+    (SDLKey_SDLK_c, c"c"),
+    (SDLKey_SDLK_d, c"d"),
+    (SDLKey_SDLK_e, c"e"),
+    (SDLKey_SDLK_f, c"f"),
+    (SDLKey_SDLK_g, c"g"),
+    (SDLKey_SDLK_h, c"h"),
+    (SDLKey_SDLK_i, c"i"),
+    (SDLKey_SDLK_j, c"j"),
+    (SDLKey_SDLK_k, c"k"),
+    (SDLKey_SDLK_l, c"l"),
+    (SDLKey_SDLK_m, c"m"),
+    (SDLKey_SDLK_n, c"n"),
+    (SDLKey_SDLK_o, c"o"),
+    (SDLKey_SDLK_p, c"p"),
+    (SDLKey_SDLK_q, c"q"),
+    (SDLKey_SDLK_r, c"r"),
+    (SDLKey_SDLK_s, c"s"),
+    (SDLKey_SDLK_t, c"t"),
+    (SDLKey_SDLK_u, c"u"),
+    (SDLKey_SDLK_v, c"v"),
+    (SDLKey_SDLK_w, c"w"),
+    (SDLKey_SDLK_x, c"x"),
+    (SDLKey_SDLK_y, c"y"),
+    (SDLKey_SDLK_z, c"z"),
+    (SDLKey_SDLK_DELETE, c"delete"),
+    (SDLKey_SDLK_KP0, c"[0]"),
+    (SDLKey_SDLK_KP1, c"[1]"),
+    (SDLKey_SDLK_KP2, c"[2]"),
+    (SDLKey_SDLK_KP3, c"[3]"),
+    (SDLKey_SDLK_KP4, c"[4]"),
+    (SDLKey_SDLK_KP5, c"[5]"),
+    (SDLKey_SDLK_KP6, c"[6]"),
+    (SDLKey_SDLK_KP7, c"[7]"),
+    (SDLKey_SDLK_KP8, c"[8]"),
+    (SDLKey_SDLK_KP9, c"[9]"),
+    (SDLKey_SDLK_KP_PERIOD, c"[.]"),
+    (SDLKey_SDLK_KP_DIVIDE, c"[/]"),
+    (SDLKey_SDLK_KP_MULTIPLY, c"[*]"),
+    (SDLKey_SDLK_KP_MINUS, c"[-]"),
+    (SDLKey_SDLK_KP_PLUS, c"[+]"),
+    (SDLKey_SDLK_KP_ENTER, c"enter"),
+    (SDLKey_SDLK_KP_EQUALS, c"equals"),
+    (SDLKey_SDLK_UP, c"up"),
+    (SDLKey_SDLK_DOWN, c"down"),
+    (SDLKey_SDLK_RIGHT, c"right"),
+    (SDLKey_SDLK_LEFT, c"left"),
+    (SDLKey_SDLK_INSERT, c"insert"),
+    (SDLKey_SDLK_HOME, c"home"),
+    (SDLKey_SDLK_END, c"end"),
+    (SDLKey_SDLK_PAGEUP, c"page up"),
+    (SDLKey_SDLK_PAGEDOWN, c"page down"),
+    (SDLKey_SDLK_F1, c"f1"),
+    (SDLKey_SDLK_F2, c"f2"),
+    (SDLKey_SDLK_F3, c"f3"),
+    (SDLKey_SDLK_F4, c"f4"),
+    (SDLKey_SDLK_F5, c"f5"),
+    (SDLKey_SDLK_F6, c"f6"),
+    (SDLKey_SDLK_F7, c"f7"),
+    (SDLKey_SDLK_F8, c"f8"),
+    (SDLKey_SDLK_F9, c"f9"),
+    (SDLKey_SDLK_F10, c"f10"),
+    (SDLKey_SDLK_F11, c"f11"),
+    (SDLKey_SDLK_F12, c"f12"),
+    (SDLKey_SDLK_F13, c"f13"),
+    (SDLKey_SDLK_F14, c"f14"),
+    (SDLKey_SDLK_F15, c"f15"),
+    (SDLKey_SDLK_NUMLOCK, c"numlock"),
+    (SDLKey_SDLK_CAPSLOCK, c"caps lock"),
+    (SDLKey_SDLK_SCROLLOCK, c"scroll lock"),
+    (SDLKey_SDLK_RSHIFT, c"right shift"),
+    (SDLKey_SDLK_LSHIFT, c"left shift"),
+    (SDLKey_SDLK_RCTRL, c"right ctrl"),
+    (SDLKey_SDLK_LCTRL, c"left ctrl"),
+    (SDLKey_SDLK_RALT, c"right alt"),
+    (SDLKey_SDLK_LALT, c"left alt"),
+    (SDLKey_SDLK_RMETA, c"right meta"),
+    (SDLKey_SDLK_LMETA, c"left meta"),
+    (SDLKey_SDLK_LSUPER, c"left super"),
+    (SDLKey_SDLK_RSUPER, c"right super"),
+    (SDLKey_SDLK_MODE, c"alt gr"),
+    (SDLKey_SDLK_COMPOSE, c"compose"),
+    (SDLKey_SDLK_HELP, c"help"),
+    (SDLKey_SDLK_PRINT, c"print screen"),
+    (SDLKey_SDLK_SYSREQ, c"sys req"),
+    (SDLKey_SDLK_BREAK, c"break"),
+    (SDLKey_SDLK_MENU, c"menu"),
+    (SDLKey_SDLK_POWER, c"power"),
+    (SDLKey_SDLK_EURO, c"euro"),
+    (SDLKey_SDLK_UNDO, c"undo"),
+];
+
+static WORLD_NAMES: LazyLock<Vec<CString>> = LazyLock::new(|| {
+    (SDLKey_SDLK_WORLD_0..=SDLKey_SDLK_WORLD_95)
+        .map(|key| CString::new(format!("world {}", key - SDLKey_SDLK_WORLD_0)).unwrap())
+        .collect()
+});
+
+pub fn key_name(key: SDLKey) -> &'static CStr {
+    if (SDLKey_SDLK_WORLD_0..=SDLKey_SDLK_WORLD_95).contains(&key) {
+        return &WORLD_NAMES[(key - SDLKey_SDLK_WORLD_0) as usize];
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map_or(c"unknown key", |(_, name)| name)
+}
+
+/// The key `key_name` calls `name`, ignoring case. For reading keys out of the config.
+pub fn key_from_name(name: &str) -> Option<SDLKey> {
+    KEY_NAMES
+        .iter()
+        .copied()
+        .chain((SDLKey_SDLK_WORLD_0..=SDLKey_SDLK_WORLD_95).map(|key| (key, key_name(key))))
+        .find(|(_, key_name)| key_name.to_string_lossy().eq_ignore_ascii_case(name))
+        .map(|(key, _)| key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_defs::SDLKey_SDLK_LAST;
+
+    #[test]
+    fn keysyms_to_keys() {
+        for (sym, key) in KEYSYMS {
+            assert_eq!(sdl_key_from_keysym(*sym), *key, "{:?}", sym);
+            assert_ne!(key_name(*key), c"unknown key", "{:?}", sym);
+        }
+        assert_eq!(
+            sdl_key_from_keysym(Keysym::adiaeresis),
+            SDLKey_SDLK_WORLD_0 + 0xe4 - 0xa0
+        );
+        assert_eq!(
+            sdl_key_from_keysym(Keysym::ISO_Level3_Shift),
+            SDLKey_SDLK_MODE
+        );
+        for sym in [Keysym::dead_grave, Keysym::dead_acute, Keysym::dead_hamza] {
+            assert_eq!(sdl_key_from_keysym(sym), SDLKey_SDLK_COMPOSE, "{:?}", sym);
+        }
+    }
+
+    #[test]
+    fn keys_to_keysyms() {
+        // Every key with a name has to be reachable from some keysym, or the game waits forever
+        // for it to be pressed.
+        for key in 1..SDLKey_SDLK_LAST {
+            if key_name(key) == c"unknown key" {
+                continue;
+            }
+            let reachable = (SDLKey_SDLK_WORLD_0..=SDLKey_SDLK_WORLD_95).contains(&key)
+                || KEYSYMS.iter().any(|(_, k)| *k == key);
+            assert!(reachable, "{} ({:?})", key, key_name(key));
+        }
+        assert_eq!(key_name(SDLKey_SDLK_WORLD_0 + 5), c"world 5");
+    }
+
+    #[test]
+    fn names_to_keys() {
+        for key in 1..SDLKey_SDLK_LAST {
+            let name = key_name(key).to_str().unwrap();
+            if name != "unknown key" {
+                assert_eq!(key_from_name(name), Some(key), "{}", name);
+            }
+        }
+        assert_eq!(key_from_name("Left Shift"), Some(SDLKey_SDLK_LSHIFT));
+        assert_eq!(key_from_name("nonsense"), None);
     }
 }